@@ -0,0 +1,95 @@
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fs, path::Path};
+
+use crate::{FRICTION, GRAVITY, MOTOR_POWER};
+
+/// Physics parameters of the game. The missing fields of a file take their default value, so
+/// that a curriculum only needs to list the parameters it changes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MountainCarConfig {
+    /// Acceleration given by the motor.
+    pub motor_power: f32,
+
+    /// Friction coefficient slowing the car down.
+    pub friction: f32,
+
+    /// Gravity pulling the car down the slopes.
+    pub gravity: f32,
+
+    /// Position of the flag ending the game.
+    pub goal: f32,
+
+    /// Lowest starting position.
+    pub start_min: f32,
+
+    /// Highest starting position, excluded.
+    pub start_max: f32,
+}
+
+impl Default for MountainCarConfig {
+    fn default() -> Self {
+        MountainCarConfig {
+            motor_power: MOTOR_POWER,
+            friction: FRICTION,
+            gravity: GRAVITY,
+            goal: 1.77,
+            start_min: 0.5,
+            start_max: 0.6,
+        }
+    }
+}
+
+impl MountainCarConfig {
+    /// Load the parameters from a TOML or a JSON file, depending on its extension, and check
+    /// them.
+    pub fn from_file<P: AsRef<Path>>(p: P) -> Result<Self, Box<dyn Error>> {
+        let content = fs::read_to_string(&p)?;
+        let config: MountainCarConfig = match p.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("toml") => toml::from_str(&content)?,
+            Some("json") => serde_json::from_str(&content)?,
+            _ => {
+                return Err(format!(
+                    "Unknown configuration format for {}: expected .toml or .json",
+                    p.as_ref().display()
+                )
+                .into())
+            }
+        };
+        config
+            .validate()
+            .map_err(|e| format!("Invalid configuration in {}: {e}", p.as_ref().display()))?;
+        Ok(config)
+    }
+
+    /// Check that the parameters are finite and that the car starts in a non-empty range
+    /// before the flag.
+    pub fn validate(&self) -> Result<(), Box<dyn Error>> {
+        let parameters = [
+            ("motor_power", self.motor_power),
+            ("friction", self.friction),
+            ("gravity", self.gravity),
+            ("goal", self.goal),
+            ("start_min", self.start_min),
+            ("start_max", self.start_max),
+        ];
+        if let Some((name, value)) = parameters.iter().find(|(_, v)| !v.is_finite()) {
+            return Err(format!("{name} is {value}").into());
+        }
+        if self.start_min >= self.start_max {
+            return Err(format!(
+                "the starting range [{}, {}) is empty",
+                self.start_min, self.start_max
+            )
+            .into());
+        }
+        if self.start_max > self.goal {
+            return Err(format!(
+                "the car can start at {} beyond the goal {}",
+                self.start_max, self.goal
+            )
+            .into());
+        }
+        Ok(())
+    }
+}
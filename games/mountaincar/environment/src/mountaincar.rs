@@ -1,8 +1,15 @@
 use std::{convert::TryFrom, error::Error};
 
 use candle_core::Tensor;
-use rand::Rng;
-use rl::mdp::MarkovDecisionProcess;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
+
+mod config;
+mod rockyroad;
+
+pub use config::MountainCarConfig;
+pub use rockyroad::RockyRoad;
 
 pub trait Ground: Send + Sync {
     // The slope of the curve at the given point
@@ -14,6 +21,7 @@ pub trait Ground: Send + Sync {
 
 // "Mountain car" decision process
 
+#[derive(Clone)]
 pub struct MountainCar<T>
 where
     T: Ground,
@@ -21,6 +29,8 @@ where
     pub pos: f32,
     pub speed: f32,
     pub ground: T,
+    pub config: MountainCarConfig,
+    rng: StdRng,
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -31,17 +41,45 @@ pub enum MountainAction {
     DoNothing = 0,
 }
 
+impl DiscreteAction for MountainAction {
+    const COUNT: usize = 3;
+
+    fn index(&self) -> usize {
+        match self {
+            MountainAction::Left => 0,
+            MountainAction::DoNothing => 1,
+            MountainAction::Right => 2,
+        }
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        match i {
+            0 => Some(MountainAction::Left),
+            1 => Some(MountainAction::DoNothing),
+            2 => Some(MountainAction::Right),
+            _ => None,
+        }
+    }
+}
+
+// Default physics parameters
 pub const MOTOR_POWER: f32 = 0.07;
 pub const FRICTION: f32 = 0.2;
 pub const GRAVITY: f32 = 0.15;
 
 impl<T: Ground> MountainCar<T> {
-    pub fn new(g: T) -> Self {
-        MountainCar {
-            pos: rand::thread_rng().gen_range(0.5..0.6),
+    /// Car on the ground with the given physics, which are checked first: an empty starting
+    /// range would make the resets panic.
+    pub fn new(g: T, config: MountainCarConfig) -> Result<Self, Box<dyn Error>> {
+        config.validate()?;
+        let mut rng = StdRng::from_entropy();
+        Ok(MountainCar {
+            pos: rng.gen_range(config.start_min..config.start_max),
             speed: 0.0,
             ground: g,
-        }
+            config,
+            rng,
+        })
     }
 }
 
@@ -49,20 +87,33 @@ impl<T: Ground> MarkovDecisionProcess for MountainCar<T> {
     type Action = MountainAction;
 
     fn reset(&mut self) {
-        self.pos = rand::thread_rng().gen_range(0.5..0.6);
+        self.pos = self
+            .rng
+            .gen_range(self.config.start_min..self.config.start_max);
+        self.speed = 0.0;
+    }
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
     }
     fn is_finished(&self) -> bool {
-        self.pos > 1.77
+        self.pos > self.config.goal
     }
-    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<f32, Box<dyn Error>> {
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
         let slope = self.ground.slope(self.pos);
+        let c = &self.config;
         self.speed += time_step
-            * (action as i8 as f32 * MOTOR_POWER - slope * GRAVITY - self.speed * FRICTION);
+            * (action as i8 as f32 * c.motor_power - slope * c.gravity - self.speed * c.friction);
         self.pos += time_step * self.speed * self.ground.derivivative(self.pos);
-        Ok(-1.0) // Reward -1 at each step
+        // Reward -1 at each step
+        Ok(Transition::new(self.feature(), -1.0, self.is_finished()))
     }
 
     fn feature(&self) -> Tensor {
         Tensor::try_from(vec![self.pos, self.speed]).unwrap()
     }
+
+    fn observation_space(&self) -> BoxSpace {
+        // Nominal range of the position until the flag and of the speed
+        BoxSpace::new(vec![0.0, -0.15], vec![self.config.goal, 0.15])
+    }
 }
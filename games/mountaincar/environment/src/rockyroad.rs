@@ -0,0 +1,39 @@
+use bevy_math::cubic_splines::{CubicBezier, CubicCurve, CubicGenerator};
+use bevy_math::{vec2, Vec2};
+
+use crate::Ground;
+
+/// Rocky road of the game: two cubic Bézier segments drawn across a window 1620 pixels wide.
+#[derive(Clone)]
+pub struct RockyRoad(pub CubicCurve<Vec2>);
+
+impl Default for RockyRoad {
+    fn default() -> Self {
+        let control_points = [
+            [
+                vec2(-810.0, -83.0), // 0.0
+                vec2(-77.0, -645.0), // -1120.0
+                vec2(311.0, -539.0), // 340.0
+                vec2(515.0, -326.0), // -30.0
+            ],
+            [
+                vec2(515.0, -326.0), // 0.0
+                vec2(703.0, -130.0), // -1120.0
+                vec2(714.0, -76.0),  // 340.0
+                vec2(810.0, -133.0), // -30.0
+            ],
+        ];
+        RockyRoad(CubicBezier::new(control_points).to_curve())
+    }
+}
+
+impl Ground for RockyRoad {
+    fn slope(&self, x: f32) -> f32 {
+        let v = self.0.velocity(x);
+        v.y / v.x
+    }
+
+    fn derivivative(&self, x: f32) -> f32 {
+        1_400.0 * self.0.velocity(x).length_recip()
+    }
+}
@@ -0,0 +1,46 @@
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+
+#[test]
+fn missing_fields_take_their_default_value() {
+    let config: MountainCarConfig = toml::from_str("motor_power = 0.05\ngravity = 0.2").unwrap();
+    assert_eq!(
+        config,
+        MountainCarConfig {
+            motor_power: 0.05,
+            gravity: 0.2,
+            ..Default::default()
+        }
+    );
+    let json: MountainCarConfig = serde_json::from_str(r#"{"goal": 1.5}"#).unwrap();
+    assert_eq!(json.goal, 1.5);
+    assert_eq!(json.friction, MountainCarConfig::default().friction);
+}
+
+#[test]
+fn invalid_files_are_rejected() {
+    let path = std::env::temp_dir().join("mountaincar_invalid_config.toml");
+    for content in [
+        "start_min = 0.6\nstart_max = 0.5",
+        "start_min = 0.5\nstart_max = 0.5",
+        "goal = 0.55",
+        "gravity = nan",
+    ] {
+        std::fs::write(&path, content).unwrap();
+        assert!(MountainCarConfig::from_file(&path).is_err(), "{content}");
+    }
+
+    std::fs::write(&path, "goal = 1.5").unwrap();
+    assert_eq!(MountainCarConfig::from_file(&path).unwrap().goal, 1.5);
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn cars_need_a_valid_configuration() {
+    let config = MountainCarConfig {
+        start_min: 0.6,
+        start_max: 0.5,
+        ..Default::default()
+    };
+    assert!(MountainCar::new(RockyRoad::default(), config).is_err());
+    assert!(MountainCar::new(RockyRoad::default(), MountainCarConfig::default()).is_ok());
+}
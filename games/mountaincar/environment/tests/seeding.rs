@@ -0,0 +1,37 @@
+use mountaincar_env::{Ground, MountainAction, MountainCar, MountainCarConfig};
+use rl::mdp::MarkovDecisionProcess;
+
+struct Valley;
+
+impl Ground for Valley {
+    fn slope(&self, x: f32) -> f32 {
+        x - 0.5
+    }
+
+    fn derivivative(&self, _x: f32) -> f32 {
+        1.0
+    }
+}
+
+fn trajectory(m: &mut MountainCar<Valley>, seed: u64) -> Vec<(f32, f32)> {
+    m.reset_with_seed(seed);
+    (0..100)
+        .map(|_| {
+            m.step(MountainAction::Right, 0.1).unwrap();
+            (m.pos, m.speed)
+        })
+        .collect()
+}
+
+#[test]
+fn identical_seeds_give_identical_trajectories() {
+    let mut m = MountainCar::new(Valley, MountainCarConfig::default()).unwrap();
+    let first = trajectory(&mut m, 42);
+    let second = trajectory(
+        &mut MountainCar::new(Valley, MountainCarConfig::default()).unwrap(),
+        42,
+    );
+    assert_eq!(first, second);
+    assert_eq!(first, trajectory(&mut m, 42));
+    assert_ne!(first, trajectory(&mut m, 43));
+}
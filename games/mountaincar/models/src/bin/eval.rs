@@ -0,0 +1,149 @@
+use clap::{Parser, ValueEnum};
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::linear::LinearAgent;
+use mountaincar_mods::mlp::MultiLayerPerceptron;
+use mountaincar_mods::tabular::Tabular;
+use rl::ai::{Agent, FileLoader, InferenceMode, ModalAgent};
+use rl::evaluation::{EvaluationReport, EvaluationSettings};
+use serde::Serialize;
+use std::error::Error;
+use std::path::PathBuf;
+
+type Game = MountainCar<RockyRoad>;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Brain {
+    Tabular,
+    Mlp,
+    Linear,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    Greedy,
+    Sampled,
+    EpsilonGreedy,
+}
+
+/// Evaluate a saved agent on Mountain Car without opening a window.
+#[derive(Parser)]
+struct Args {
+    /// Safetensors file storing the agent.
+    path: PathBuf,
+
+    /// Type of the agent stored in the file.
+    #[arg(short, long, value_enum)]
+    brain: Brain,
+
+    /// Way the agent picks its actions.
+    #[arg(short, long, value_enum, default_value_t = Mode::Greedy)]
+    mode: Mode,
+
+    /// Probability of a random action in the epsilon-greedy mode.
+    #[arg(long, default_value_t = 0.05)]
+    epsilon: f32,
+
+    /// Number of evaluation games.
+    #[arg(short = 'n', long, default_value_t = 100)]
+    episodes: u32,
+
+    /// Maximal number of steps of a game before it is counted as a failure.
+    #[arg(long, default_value_t = 5_000)]
+    max_steps: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the evaluation. The stochastic modes are reproducible with a single thread.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Number of threads playing the games.
+    #[arg(short = 'j', long, default_value_t = 1)]
+    threads: usize,
+
+    /// Print the statistics as JSON.
+    #[arg(long)]
+    json: bool,
+}
+
+/// Statistics of the evaluation games.
+#[derive(Serialize)]
+struct Summary {
+    episodes: usize,
+    mean_return: f32,
+    std_return: f32,
+    confidence_interval: (f32, f32),
+    min_return: f32,
+    max_return: f32,
+    success_rate: f32,
+    mean_length: f32,
+}
+
+impl From<&EvaluationReport> for Summary {
+    fn from(r: &EvaluationReport) -> Self {
+        Summary {
+            episodes: r.episodes.len(),
+            mean_return: r.mean(),
+            std_return: r.std(),
+            confidence_interval: r.confidence_interval(),
+            min_return: r.min(),
+            max_return: r.max(),
+            success_rate: r.success_rate(),
+            mean_length: r.mean_length(),
+        }
+    }
+}
+
+fn evaluate<A: FileLoader<Game> + Sync>(args: &Args) -> Result<EvaluationReport, Box<dyn Error>> {
+    let mode = match args.mode {
+        Mode::Greedy => InferenceMode::Greedy,
+        Mode::Sampled => InferenceMode::Sampled,
+        Mode::EpsilonGreedy => InferenceMode::EpsilonGreedy(args.epsilon),
+    };
+    let agent = ModalAgent::new(A::from_file(args.path.clone())?, mode, args.seed);
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let env = MountainCar::new(RockyRoad::default(), config)?;
+    let settings = EvaluationSettings {
+        nb_games: args.episodes,
+        time_step: args.time_step,
+        max_steps: args.max_steps,
+        seed: args.seed,
+    };
+    agent.evaluate_parallel(&env, &settings, args.threads)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let report = match args.brain {
+        Brain::Tabular => evaluate::<Tabular>(&args)?,
+        Brain::Mlp => evaluate::<MultiLayerPerceptron<2, 3>>(&args)?,
+        Brain::Linear => evaluate::<LinearAgent>(&args)?,
+    };
+    let summary = Summary::from(&report);
+
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&summary)?);
+    } else {
+        println!("Episodes:       {}", summary.episodes);
+        println!(
+            "Return:         {:.2} ± {:.2} (min {:.2}, max {:.2})",
+            summary.mean_return, summary.std_return, summary.min_return, summary.max_return
+        );
+        println!(
+            "95% interval:   [{:.2}, {:.2}]",
+            summary.confidence_interval.0, summary.confidence_interval.1
+        );
+        println!("Success rate:   {:.1}%", 100.0 * summary.success_rate);
+        println!("Episode length: {:.1}", summary.mean_length);
+    }
+    Ok(())
+}
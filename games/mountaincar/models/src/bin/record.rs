@@ -0,0 +1,114 @@
+use clap::{Parser, ValueEnum};
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::linear::LinearAgent;
+use mountaincar_mods::mlp::MultiLayerPerceptron;
+use mountaincar_mods::scene::{MountainCarScene, HEIGHT, WIDTH};
+use mountaincar_mods::tabular::Tabular;
+use recorder::{RecordSettings, Recording};
+use rl::ai::{FileLoader, InferenceMode, ModalAgent};
+use std::error::Error;
+use std::path::PathBuf;
+
+type Game = MountainCar<RockyRoad>;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Brain {
+    Tabular,
+    Mlp,
+    Linear,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    Greedy,
+    Sampled,
+    EpsilonGreedy,
+}
+
+/// Record a game of Mountain Car played by a saved agent, without opening a window.
+#[derive(Parser)]
+struct Args {
+    /// Safetensors file storing the agent.
+    path: PathBuf,
+
+    /// Type of the agent stored in the file.
+    #[arg(short, long, value_enum)]
+    brain: Brain,
+
+    /// Animated GIF written if the path ends with `.gif`, directory of numbered PNG frames
+    /// otherwise.
+    #[arg(short, long, default_value = "mountaincar.gif")]
+    output: PathBuf,
+
+    /// Way the agent picks its actions.
+    #[arg(short, long, value_enum, default_value_t = Mode::Greedy)]
+    mode: Mode,
+
+    /// Probability of a random action in the epsilon-greedy mode.
+    #[arg(long, default_value_t = 0.05)]
+    epsilon: f32,
+
+    /// Size of the frames relative to the window of the game.
+    #[arg(long, default_value_t = 0.5)]
+    scale: f32,
+
+    /// Number of steps between two frames.
+    #[arg(long, default_value_t = 1)]
+    frame_every: usize,
+
+    /// Maximal number of steps of the game.
+    #[arg(long, default_value_t = 1_000)]
+    max_steps: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the game and of the stochastic modes.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+}
+
+fn record<A: FileLoader<Game>>(args: &Args) -> Result<Recording, Box<dyn Error>> {
+    let mode = match args.mode {
+        Mode::Greedy => InferenceMode::Greedy,
+        Mode::Sampled => InferenceMode::Sampled,
+        Mode::EpsilonGreedy => InferenceMode::EpsilonGreedy(args.epsilon),
+    };
+    let agent = ModalAgent::new(A::from_file(args.path.clone())?, mode, args.seed);
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let mut env = MountainCar::new(RockyRoad::default(), config)?;
+    let settings = RecordSettings {
+        width: (WIDTH * args.scale).round() as u32,
+        height: (HEIGHT * args.scale).round() as u32,
+        scale: args.scale,
+        time_step: args.time_step,
+        max_steps: args.max_steps,
+        frame_every: args.frame_every,
+        seed: args.seed,
+    };
+    recorder::record(&agent, &mut env, &MountainCarScene, &settings)
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let recording = match args.brain {
+        Brain::Tabular => record::<Tabular>(&args)?,
+        Brain::Mlp => record::<MultiLayerPerceptron<2, 3>>(&args)?,
+        Brain::Linear => record::<LinearAgent>(&args)?,
+    };
+    recording.save(&args.output)?;
+    println!(
+        "{} frames saved in {}",
+        recording.frames.len(),
+        args.output.display()
+    );
+    Ok(())
+}
@@ -0,0 +1,81 @@
+use clap::Parser;
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::mlp::MultiLayerPerceptron;
+use rl::trainer::dqn::DqnTrainer;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Train a perceptron playing Mountain Car with a Deep Q-Network and save it in a safetensors
+/// file.
+#[derive(Parser)]
+struct Args {
+    /// File where the network is saved, also used for the periodic checkpoints.
+    #[arg(short, long, default_value = "mlp.safetensors")]
+    output: PathBuf,
+
+    /// Total number of steps played during the training.
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    steps: usize,
+
+    /// Number of copies of the game played together.
+    #[arg(long, default_value_t = 1)]
+    envs: usize,
+
+    /// Sizes of the internal layers of the perceptron.
+    #[arg(long, value_delimiter = ',', default_values_t = [64, 64])]
+    hidden_layers: Vec<usize>,
+
+    /// Learning rate of the optimizer.
+    #[arg(long, default_value_t = 1e-3)]
+    learning_rate: f64,
+
+    /// Number of steps over which the exploration rate decreases.
+    #[arg(long, default_value_t = 20_000)]
+    epsilon_decay_steps: usize,
+
+    /// Normalize the features with their running mean and standard deviation.
+    #[arg(long)]
+    normalize: bool,
+
+    /// Number of steps played between two checkpoints.
+    #[arg(long, default_value_t = 10_000)]
+    checkpoint_every: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = DqnTrainer {
+        hidden_layers: args.hidden_layers,
+        learning_rate: args.learning_rate,
+        epsilon_decay_steps: args.epsilon_decay_steps,
+        total_steps: args.steps,
+        nb_envs: args.envs,
+        time_step: args.time_step,
+        normalize_observations: args.normalize,
+        checkpoint: Some(args.output.clone()),
+        checkpoint_every: args.checkpoint_every,
+        seed: args.seed,
+        ..Default::default()
+    };
+
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let env = MountainCar::new(RockyRoad::default(), config)?;
+    let _: MultiLayerPerceptron<2, 3> = trainer.train(&env)?;
+    println!("Network saved in {}", args.output.display());
+    Ok(())
+}
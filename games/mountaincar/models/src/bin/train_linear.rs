@@ -0,0 +1,120 @@
+use clap::{Parser, ValueEnum};
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::linear::{FeatureMap, SarsaLambdaTrainer};
+use rl::mdp::MarkovDecisionProcess;
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Features {
+    TileCoding,
+    Rbf,
+}
+
+/// Train a linear agent playing Mountain Car with SARSA(λ) and save it in a safetensors file.
+#[derive(Parser)]
+struct Args {
+    /// File where the agent is saved.
+    #[arg(short, long, default_value = "linear.safetensors")]
+    output: PathBuf,
+
+    /// Features of the states.
+    #[arg(short, long, value_enum, default_value_t = Features::TileCoding)]
+    features: Features,
+
+    /// Number of shifted grids of the tile coding.
+    #[arg(long, default_value_t = 8)]
+    tilings: usize,
+
+    /// Number of tiles along the position and the speed in each grid.
+    #[arg(long, default_value_t = 8)]
+    tiles: usize,
+
+    /// Number of radial basis centers along the position and the speed.
+    #[arg(long, default_value_t = 10)]
+    centers: usize,
+
+    /// Width of the radial basis functions, in units of the spacing between the centers.
+    #[arg(long, default_value_t = 1.0)]
+    width: f32,
+
+    /// Number of training games.
+    #[arg(short, long, default_value_t = 100)]
+    episodes: u32,
+
+    /// Maximal number of steps of a training game.
+    #[arg(long, default_value_t = 10_000)]
+    max_steps: usize,
+
+    /// Step size of the updates, divided by the number of active features.
+    #[arg(long, default_value_t = 0.5)]
+    learning_rate: f32,
+
+    /// Decay of the eligibility traces.
+    #[arg(long, default_value_t = 0.9)]
+    lambda: f32,
+
+    /// Probability of exploring a random action.
+    #[arg(long, default_value_t = 0.0)]
+    epsilon: f32,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Lower bounds of the features. Defaults to the bounds of the observation space.
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+    low: Option<Vec<f32>>,
+
+    /// Upper bounds of the features. Defaults to the bounds of the observation space.
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+    high: Option<Vec<f32>>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = SarsaLambdaTrainer {
+        learning_rate: args.learning_rate,
+        lambda: args.lambda,
+        epsilon: args.epsilon,
+        nb_episodes: args.episodes,
+        max_steps: args.max_steps,
+        time_step: args.time_step,
+        seed: args.seed,
+        ..Default::default()
+    };
+
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let mut env = MountainCar::new(RockyRoad::default(), config)?;
+    let space = env.observation_space();
+    let low = args.low.unwrap_or(space.low);
+    let high = args.high.unwrap_or(space.high);
+    let features = match args.features {
+        Features::TileCoding => FeatureMap::TileCoding {
+            low,
+            high,
+            tilings: args.tilings,
+            tiles: args.tiles,
+        },
+        Features::Rbf => FeatureMap::Rbf {
+            low,
+            high,
+            centers: args.centers,
+            width: args.width,
+        },
+    };
+    trainer.train(&mut env, features)?.save(&args.output)?;
+    println!("Agent saved in {}", args.output.display());
+    Ok(())
+}
@@ -0,0 +1,105 @@
+use clap::Parser;
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use rl::trainer::ppo::{ActorCritic, PpoTrainer};
+use rl::wrappers::FrameSkip;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Train an actor and a critic playing Mountain Car with PPO and save them in safetensors files.
+/// The actor file is the one loaded by the game and by the evaluation.
+#[derive(Parser)]
+struct Args {
+    /// File where the actor is saved, also used for the periodic checkpoints. The critic is saved
+    /// next to it.
+    #[arg(short, long, default_value = "ppo.safetensors")]
+    output: PathBuf,
+
+    /// Total number of decisions taken during the training.
+    #[arg(short = 'n', long, default_value_t = 100_000)]
+    steps: usize,
+
+    /// Number of copies of the game played together.
+    #[arg(long, default_value_t = 4)]
+    envs: usize,
+
+    /// Number of decisions taken by each game between two updates.
+    #[arg(long, default_value_t = 512)]
+    rollout_steps: usize,
+
+    /// Sizes of the internal layers of the actor and of the critic.
+    #[arg(long, value_delimiter = ',', default_values_t = [64, 64])]
+    hidden_layers: Vec<usize>,
+
+    /// Learning rate of the optimizer.
+    #[arg(long, default_value_t = 3e-4)]
+    learning_rate: f64,
+
+    /// Weight of the entropy bonus.
+    #[arg(long, default_value_t = 0.01)]
+    entropy_coef: f32,
+
+    /// Number of steps each sampled action is played during the training, which helps the
+    /// random policy of the beginning reach the flag. The evaluation and the renderer pick an
+    /// action at every step, so a policy trained with a frame skip is played at another pace.
+    #[arg(long, default_value_t = 1)]
+    frame_skip: usize,
+
+    /// Normalize the features with their running mean and standard deviation.
+    #[arg(long)]
+    normalize: bool,
+
+    /// Start from the networks saved in the output files.
+    #[arg(long)]
+    resume: bool,
+
+    /// Number of updates between two checkpoints.
+    #[arg(long, default_value_t = 10)]
+    checkpoint_every: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = PpoTrainer {
+        hidden_layers: args.hidden_layers,
+        learning_rate: args.learning_rate,
+        rollout_steps: args.rollout_steps,
+        entropy_coef: args.entropy_coef,
+        total_steps: args.steps,
+        nb_envs: args.envs,
+        time_step: args.time_step,
+        normalize_observations: args.normalize,
+        checkpoint: Some(args.output.clone()),
+        checkpoint_every: args.checkpoint_every,
+        resume: args.resume,
+        seed: args.seed,
+        ..Default::default()
+    };
+
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let env = FrameSkip::new(
+        MountainCar::new(RockyRoad::default(), config)?,
+        args.frame_skip,
+    );
+    let _: ActorCritic<2, 3> = trainer.train(&env)?;
+    println!(
+        "Actor saved in {} and critic in {}",
+        args.output.display(),
+        ActorCritic::<2, 3>::critic_path(&args.output).display()
+    );
+    Ok(())
+}
@@ -0,0 +1,101 @@
+use clap::Parser;
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::mlp::MultiLayerPerceptron;
+use rl::trainer::reinforce::ReinforceTrainer;
+use rl::wrappers::FrameSkip;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Train a perceptron policy playing Mountain Car with REINFORCE and save it in a safetensors
+/// file.
+#[derive(Parser)]
+struct Args {
+    /// File where the policy is saved, also used for the periodic checkpoints.
+    #[arg(short, long, default_value = "reinforce.safetensors")]
+    output: PathBuf,
+
+    /// Number of gradient steps.
+    #[arg(short = 'n', long, default_value_t = 200)]
+    updates: usize,
+
+    /// Number of games played for each gradient step.
+    #[arg(long, default_value_t = 8)]
+    batch_episodes: usize,
+
+    /// Sizes of the internal layers of the policy and of the baseline.
+    #[arg(long, value_delimiter = ',', default_values_t = [32, 32])]
+    hidden_layers: Vec<usize>,
+
+    /// Learning rate of the policy.
+    #[arg(long, default_value_t = 1e-3)]
+    learning_rate: f64,
+
+    /// Discount factor of the future rewards.
+    #[arg(long, default_value_t = 0.99)]
+    discount: f32,
+
+    /// Use the raw returns instead of subtracting a learned baseline.
+    #[arg(long)]
+    no_baseline: bool,
+
+    /// Number of steps each sampled action is played during the training, which helps the
+    /// random policy of the beginning reach the flag. The evaluation and the renderer pick an
+    /// action at every step, so a policy trained with a frame skip is played at another pace.
+    #[arg(long, default_value_t = 1)]
+    frame_skip: usize,
+
+    /// Maximal number of decisions of a training game.
+    #[arg(long, default_value_t = 1_000)]
+    max_steps: usize,
+
+    /// Normalize the features with their running mean and standard deviation.
+    #[arg(long)]
+    normalize: bool,
+
+    /// Number of gradient steps between two checkpoints.
+    #[arg(long, default_value_t = 20)]
+    checkpoint_every: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = ReinforceTrainer {
+        hidden_layers: args.hidden_layers,
+        learning_rate: args.learning_rate,
+        baseline: !args.no_baseline,
+        discount: args.discount,
+        batch_episodes: args.batch_episodes,
+        nb_updates: args.updates,
+        max_episode_steps: args.max_steps,
+        time_step: args.time_step,
+        normalize_observations: args.normalize,
+        checkpoint: Some(args.output.clone()),
+        checkpoint_every: args.checkpoint_every,
+        seed: args.seed,
+        ..Default::default()
+    };
+
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let mut env = FrameSkip::new(
+        MountainCar::new(RockyRoad::default(), config)?,
+        args.frame_skip,
+    );
+    let _: MultiLayerPerceptron<2, 3> = trainer.train(&mut env)?;
+    println!("Policy saved in {}", args.output.display());
+    Ok(())
+}
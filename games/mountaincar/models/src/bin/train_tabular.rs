@@ -0,0 +1,105 @@
+use clap::{Parser, ValueEnum};
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use rl::discretizer::Discretizer;
+use rl::mdp::MarkovDecisionProcess;
+use rl::tabular::Tabular;
+use rl::trainer::tabular::{TabularTrainer, TdMethod};
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Method {
+    QLearning,
+    Sarsa,
+}
+
+/// Train a tabular agent playing Mountain Car and save it in a safetensors file.
+#[derive(Parser)]
+struct Args {
+    /// File where the table is saved.
+    #[arg(short, long, default_value = "tabular.safetensors")]
+    output: PathBuf,
+
+    /// Temporal-difference method.
+    #[arg(short, long, value_enum, default_value_t = Method::QLearning)]
+    method: Method,
+
+    /// Number of training games.
+    #[arg(short, long, default_value_t = 300)]
+    episodes: u32,
+
+    /// Maximal number of steps of a training game.
+    #[arg(long, default_value_t = 100_000)]
+    max_steps: usize,
+
+    /// Step size of the updates.
+    #[arg(long, default_value_t = 0.1)]
+    learning_rate: f32,
+
+    /// Discount factor of the future rewards.
+    #[arg(long, default_value_t = 0.999)]
+    discount: f32,
+
+    /// Probability of exploring a random action.
+    #[arg(long, default_value_t = 0.01)]
+    epsilon: f32,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+
+    /// TOML or JSON file with the physics parameters of the game.
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+
+    /// Number of bins of the grid along the position and the speed.
+    #[arg(long, value_delimiter = ',', default_values_t = [10, 10])]
+    bins: Vec<usize>,
+
+    /// Lower bounds of the grid. Defaults to the bounds of the observation space.
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+    low: Option<Vec<f32>>,
+
+    /// Upper bounds of the grid. Defaults to the bounds of the observation space.
+    #[arg(long, value_delimiter = ',', allow_negative_numbers = true)]
+    high: Option<Vec<f32>>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = TabularTrainer {
+        method: match args.method {
+            Method::QLearning => TdMethod::QLearning,
+            Method::Sarsa => TdMethod::Sarsa,
+        },
+        learning_rate: args.learning_rate,
+        discount: args.discount,
+        epsilon: args.epsilon,
+        nb_episodes: args.episodes,
+        max_steps: args.max_steps,
+        time_step: args.time_step,
+        seed: args.seed,
+    };
+
+    let config = match &args.config {
+        Some(p) => MountainCarConfig::from_file(p)?,
+        None => MountainCarConfig::default(),
+    };
+    let mut env = MountainCar::new(RockyRoad::default(), config)?;
+    let space = env.observation_space();
+    let discretizer = Discretizer::new(
+        args.low.unwrap_or(space.low),
+        args.high.unwrap_or(space.high),
+        args.bins,
+    )?;
+    let q = trainer.train(&mut env, discretizer.nb_states(), |x| {
+        Ok(discretizer.index_tensor(x)?)
+    })?;
+    Tabular::new(q, discretizer)?.save(&args.output)?;
+    println!("Table saved in {}", args.output.display());
+    Ok(())
+}
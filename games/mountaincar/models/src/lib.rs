@@ -1,2 +1,5 @@
+pub mod linear;
 pub mod mlp;
+pub mod phase_plane;
+pub mod scene;
 pub mod tabular;
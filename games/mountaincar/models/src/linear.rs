@@ -0,0 +1,469 @@
+//! Linear action values over tile-coding or radial basis features, trained with SARSA(λ).
+//!
+//! The agents are saved in safetensors files with the following schema:
+//! - `weights`, the `(nb_actions, nb_features)` weights of the action values,
+//! - `features.kind`, `tile_coding` or `rbf`, as `u8` bytes,
+//! - `features.low` and `features.high`, the box covered by the features,
+//! - `features.tilings` and `features.tiles` (`u32`) for tile coding, `features.centers`
+//!   (`u32`) and `features.width` (`f32`) for radial basis functions,
+//! - `metadata.model`, the kind of model, `linear`, as `u8` bytes.
+use candle_core::{safetensors, DType, Device, Tensor};
+use rand::{rngs::StdRng, SeedableRng};
+use rl::ai::{
+    epsilon_greedy_index, greedy_index, Agent, FileLoader, FlatParameters, QFunction, ValueFunction,
+};
+use rl::error::{self, LoadError};
+use rl::mdp::MarkovDecisionProcess;
+use rl::space::DiscreteAction;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::path::Path;
+
+use mountaincar_env::{Ground, MountainAction, MountainCar};
+
+/// Kind of model written in the files.
+pub const MODEL_KIND: &str = "linear";
+
+/// Features computed from the state of the game.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FeatureMap {
+    /// Several uniform grids over the box, each one shifted by a fraction of a tile. Each grid
+    /// activates the tile holding the state.
+    TileCoding {
+        /// Lower bounds of the box.
+        low: Vec<f32>,
+        /// Upper bounds of the box.
+        high: Vec<f32>,
+        /// Number of grids.
+        tilings: usize,
+        /// Number of tiles along each dimension of a grid.
+        tiles: usize,
+    },
+
+    /// Gaussian bumps centered on a uniform grid over the box, normalized to sum to one.
+    Rbf {
+        /// Lower bounds of the box.
+        low: Vec<f32>,
+        /// Upper bounds of the box.
+        high: Vec<f32>,
+        /// Number of centers along each dimension.
+        centers: usize,
+        /// Standard deviation of the bumps, in units of the spacing between the centers.
+        width: f32,
+    },
+}
+
+impl FeatureMap {
+    /// Number of features.
+    pub fn len(&self) -> usize {
+        match self {
+            // Shifted grids need one more tile along each dimension
+            FeatureMap::TileCoding {
+                low,
+                tilings,
+                tiles,
+                ..
+            } => tilings * (tiles + 1).pow(low.len() as u32),
+            FeatureMap::Rbf { low, centers, .. } => centers.pow(low.len() as u32),
+        }
+    }
+
+    /// Indicate if there is no feature.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Sum of the features of every state, used to scale the learning rate.
+    pub fn nb_active(&self) -> usize {
+        match self {
+            FeatureMap::TileCoding { tilings, .. } => *tilings,
+            // The normalized bumps sum to one
+            FeatureMap::Rbf { .. } => 1,
+        }
+    }
+
+    /// Non-zero features of the state, as pairs of index and value.
+    pub fn features(&self, x: &[f32]) -> Vec<(usize, f32)> {
+        match self {
+            FeatureMap::TileCoding {
+                low,
+                high,
+                tilings,
+                tiles,
+            } => (0..*tilings)
+                .map(|k| {
+                    // The d-th coordinate of the k-th grid is shifted by k (2d + 1) / tilings
+                    // of a tile, to spread the grids asymmetrically
+                    let index = x.iter().zip(low.iter().zip(high)).enumerate().fold(
+                        0,
+                        |index, (d, (x, (low, high)))| {
+                            let shift = (k * (2 * d + 1)) as f32 / *tilings as f32;
+                            let scaled = (x - low) / (high - low) * *tiles as f32 + shift.fract();
+                            index * (tiles + 1) + scaled.floor().clamp(0.0, *tiles as f32) as usize
+                        },
+                    );
+                    (k * (tiles + 1).pow(x.len() as u32) + index, 1.0)
+                })
+                .collect(),
+            FeatureMap::Rbf {
+                low,
+                high,
+                centers,
+                width,
+            } => {
+                let distances: Vec<f32> = (0..self.len())
+                    .map(|i| {
+                        // Coordinates of the i-th center on the grid, the last one varying the fastest
+                        let mut rest = i;
+                        let mut distance = 0.0;
+                        for d in (0..x.len()).rev() {
+                            let c = rest % centers;
+                            rest /= centers;
+                            let spacing = (high[d] - low[d]) / (centers - 1).max(1) as f32;
+                            let center = low[d] + c as f32 * spacing;
+                            distance += ((x[d] - center) / (width * spacing)).powi(2);
+                        }
+                        distance
+                    })
+                    .collect();
+                // Far from every center, the bumps would all round to zero: measuring the
+                // distances from the nearest center keeps its bump at one
+                let nearest = distances.iter().copied().fold(f32::INFINITY, f32::min);
+                let bumps: Vec<f32> = distances
+                    .into_iter()
+                    .map(|distance| (-0.5 * (distance - nearest)).exp())
+                    .collect();
+                let total: f32 = bumps.iter().sum();
+                bumps
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, b)| (i, b / total))
+                    .collect()
+            }
+        }
+    }
+
+    fn insert_tensors(&self, h: &mut HashMap<String, Tensor>) -> candle_core::Result<()> {
+        let (kind, low, high) = match self {
+            FeatureMap::TileCoding {
+                low,
+                high,
+                tilings,
+                tiles,
+            } => {
+                h.insert(
+                    "features.tilings".to_owned(),
+                    Tensor::new(&[*tilings as u32], &Device::Cpu)?,
+                );
+                h.insert(
+                    "features.tiles".to_owned(),
+                    Tensor::new(&[*tiles as u32], &Device::Cpu)?,
+                );
+                ("tile_coding", low, high)
+            }
+            FeatureMap::Rbf {
+                low,
+                high,
+                centers,
+                width,
+            } => {
+                h.insert(
+                    "features.centers".to_owned(),
+                    Tensor::new(&[*centers as u32], &Device::Cpu)?,
+                );
+                h.insert(
+                    "features.width".to_owned(),
+                    Tensor::new(&[*width], &Device::Cpu)?,
+                );
+                ("rbf", low, high)
+            }
+        };
+        h.insert("features.kind".to_owned(), error::string_tensor(kind));
+        h.insert(
+            "features.low".to_owned(),
+            Tensor::new(low.as_slice(), &Device::Cpu)?,
+        );
+        h.insert(
+            "features.high".to_owned(),
+            Tensor::new(high.as_slice(), &Device::Cpu)?,
+        );
+        Ok(())
+    }
+
+    // Read the features of a state with `dim` coordinates
+    fn take_tensors(h: &mut HashMap<String, Tensor>, dim: usize) -> Result<Self, LoadError> {
+        let kind = error::tensor_string("features.kind", &error::take_tensor(h, "features.kind")?)?;
+        let low = error::take_tensor(h, "features.low")?;
+        let high = error::take_tensor(h, "features.high")?;
+        for (name, t) in [("features.low", &low), ("features.high", &high)] {
+            if t.dims() != [dim] {
+                return Err(LoadError::ShapeMismatch {
+                    name: name.to_owned(),
+                    expected: vec![dim],
+                    found: t.dims().to_vec(),
+                });
+            }
+        }
+        let low = low.to_dtype(DType::F32)?.to_vec1::<f32>()?;
+        let high = high.to_dtype(DType::F32)?.to_vec1::<f32>()?;
+        let mut scalar = |name: &str| -> Result<f32, LoadError> {
+            let t = error::take_tensor(h, name)?;
+            if t.dims() != [1] {
+                return Err(LoadError::ShapeMismatch {
+                    name: name.to_owned(),
+                    expected: vec![1],
+                    found: t.dims().to_vec(),
+                });
+            }
+            Ok(t.to_dtype(DType::F32)?.to_vec1::<f32>()?[0])
+        };
+        match kind.as_str() {
+            "tile_coding" => Ok(FeatureMap::TileCoding {
+                tilings: scalar("features.tilings")? as usize,
+                tiles: scalar("features.tiles")? as usize,
+                low,
+                high,
+            }),
+            "rbf" => Ok(FeatureMap::Rbf {
+                centers: scalar("features.centers")? as usize,
+                width: scalar("features.width")?,
+                low,
+                high,
+            }),
+            _ => Err(LoadError::InvalidMetadata(format!(
+                "unknown features {kind}"
+            ))),
+        }
+    }
+}
+
+/// Agent whose action values are linear in the features of the state.
+#[derive(Debug, Clone)]
+pub struct LinearAgent {
+    /// Features of the states.
+    pub features: FeatureMap,
+
+    /// Number of actions.
+    pub nb_actions: usize,
+
+    /// Weights of the features for each action, the action varying the slowest.
+    pub weights: Vec<f32>,
+}
+
+impl LinearAgent {
+    /// Agent whose action values are all zero.
+    pub fn new(features: FeatureMap, nb_actions: usize) -> Self {
+        let weights = vec![0.0; nb_actions * features.len()];
+        LinearAgent {
+            features,
+            nb_actions,
+            weights,
+        }
+    }
+
+    /// Value of each action given the non-zero features of a state.
+    pub fn action_values(&self, phi: &[(usize, f32)]) -> Vec<f32> {
+        let n = self.features.len();
+        (0..self.nb_actions)
+            .map(|a| phi.iter().map(|(i, v)| self.weights[a * n + i] * v).sum())
+            .collect()
+    }
+
+    /// Index of the best action given the non-zero features of a state. Ties go to the lowest
+    /// index.
+    pub fn greedy(&self, phi: &[(usize, f32)]) -> usize {
+        greedy_index(&self.action_values(phi))
+    }
+
+    /// Save the agent in a safetensors file.
+    pub fn save<P: AsRef<Path>>(&self, p: P) -> candle_core::Result<()> {
+        let mut h = HashMap::new();
+        h.insert(
+            "weights".to_owned(),
+            Tensor::from_slice(
+                &self.weights,
+                (self.nb_actions, self.features.len()),
+                &Device::Cpu,
+            )?,
+        );
+        self.features.insert_tensors(&mut h)?;
+        h.insert(
+            error::MODEL_KIND_KEY.to_owned(),
+            error::string_tensor(MODEL_KIND),
+        );
+        safetensors::save(&h, p)
+    }
+}
+
+impl<T: Ground> Agent<MountainCar<T>> for LinearAgent {
+    fn policy(&self, e: &MountainCar<T>) -> Result<MountainAction, Box<dyn Error>> {
+        let phi = self.features.features(&[e.pos, e.speed]);
+        MountainAction::from_index(self.greedy(&phi))
+            .ok_or_else(|| "No action for this index".into())
+    }
+}
+
+impl<T: Ground> QFunction<MountainCar<T>> for LinearAgent {
+    fn q_values(&self, e: &MountainCar<T>) -> Result<Vec<f32>, Box<dyn Error>> {
+        Ok(self.action_values(&self.features.features(&[e.pos, e.speed])))
+    }
+}
+
+impl<T: Ground> ValueFunction<MountainCar<T>> for LinearAgent {
+    fn value(&self, e: &MountainCar<T>) -> Result<f32, Box<dyn Error>> {
+        let q = self.q_values(e)?;
+        Ok(q.into_iter().fold(f32::NEG_INFINITY, f32::max))
+    }
+}
+
+impl FlatParameters for LinearAgent {
+    fn parameters(&self) -> Result<Vec<f32>, Box<dyn Error>> {
+        Ok(self.weights.clone())
+    }
+
+    fn with_parameters(&self, parameters: &[f32]) -> Result<Self, Box<dyn Error>> {
+        if parameters.len() != self.weights.len() {
+            return Err(format!(
+                "Linear agent with {} weights given {} parameters",
+                self.weights.len(),
+                parameters.len()
+            )
+            .into());
+        }
+        Ok(LinearAgent {
+            weights: parameters.to_vec(),
+            ..self.clone()
+        })
+    }
+}
+
+impl TryFrom<&mut HashMap<String, Tensor>> for LinearAgent {
+    type Error = LoadError;
+
+    fn try_from(h: &mut HashMap<String, Tensor>) -> Result<Self, Self::Error> {
+        error::check_model_kind(h, MODEL_KIND)?;
+        // The features are computed from the position and the speed of the car
+        let features = FeatureMap::take_tensors(h, 2)?;
+        let weights = error::take_tensor(h, "weights")?;
+        // One row of weights for each action of the car
+        if weights.dims() != [MountainAction::COUNT, features.len()] {
+            return Err(LoadError::ShapeMismatch {
+                name: "weights".to_owned(),
+                expected: vec![MountainAction::COUNT, features.len()],
+                found: weights.dims().to_vec(),
+            });
+        }
+        Ok(LinearAgent {
+            features,
+            nb_actions: MountainAction::COUNT,
+            weights: weights.to_dtype(DType::F32)?.flatten_all()?.to_vec1()?,
+        })
+    }
+}
+
+impl<T: Ground> FileLoader<MountainCar<T>> for LinearAgent {}
+
+/// Hyper-parameters of the semi-gradient SARSA(λ) trainer.
+#[derive(Debug, Clone)]
+pub struct SarsaLambdaTrainer {
+    /// Step size of the updates, divided by the number of active features.
+    pub learning_rate: f32,
+
+    /// Discount factor of the future rewards.
+    pub discount: f32,
+
+    /// Decay of the eligibility traces.
+    pub lambda: f32,
+
+    /// Probability of taking a random action instead of the greedy one.
+    pub epsilon: f32,
+
+    /// Number of games played.
+    pub nb_episodes: u32,
+
+    /// Maximal number of steps of a game before it is cut.
+    pub max_steps: usize,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Seed of the exploration and of the MDP. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for SarsaLambdaTrainer {
+    fn default() -> Self {
+        SarsaLambdaTrainer {
+            learning_rate: 0.5,
+            discount: 1.0,
+            lambda: 0.9,
+            epsilon: 0.0,
+            nb_episodes: 100,
+            max_steps: 10_000,
+            time_step: 0.1,
+            seed: None,
+        }
+    }
+}
+
+impl SarsaLambdaTrainer {
+    /// Train a linear agent over the given features. The action values start at zero, which is
+    /// optimistic for rewards of -1 per step and drives the exploration.
+    pub fn train<T>(&self, e: &mut T, features: FeatureMap) -> Result<LinearAgent, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess,
+    {
+        let mut agent = LinearAgent::new(features, e.action_space().n());
+        let n = agent.features.len();
+        let alpha = self.learning_rate / agent.features.nb_active() as f32;
+        let mut rng = match self.seed {
+            Some(s) => {
+                e.seed(s);
+                StdRng::seed_from_u64(s)
+            }
+            None => StdRng::from_entropy(),
+        };
+
+        let mut traces = vec![0.0; agent.weights.len()];
+        for _ in 0..self.nb_episodes {
+            e.reset();
+            traces.iter_mut().for_each(|z| *z = 0.0);
+            let mut phi = agent.features.features(&e.feature().to_vec1::<f32>()?);
+            let mut a = epsilon_greedy_index(&agent.action_values(&phi), self.epsilon, &mut rng);
+            for _ in 0..self.max_steps {
+                let action = T::Action::from_index(a).ok_or("No action for this index")?;
+                let transition = e.step(action, self.time_step)?;
+
+                // Replacing traces: each trace is the larger of its decayed value and the feature
+                traces
+                    .iter_mut()
+                    .for_each(|z| *z *= self.discount * self.lambda);
+                for &(i, v) in &phi {
+                    let z = &mut traces[a * n + i];
+                    *z = z.max(v);
+                }
+
+                let mut delta = transition.reward - agent.action_values(&phi)[a];
+                let phi_next = agent
+                    .features
+                    .features(&transition.feature.to_vec1::<f32>()?);
+                let q_next = agent.action_values(&phi_next);
+                let a_next = epsilon_greedy_index(&q_next, self.epsilon, &mut rng);
+                // Terminal states have no future rewards to bootstrap on
+                if !transition.terminated {
+                    delta += self.discount * q_next[a_next];
+                }
+                for (w, z) in agent.weights.iter_mut().zip(&traces) {
+                    *w += alpha * delta * z;
+                }
+
+                if transition.is_done() {
+                    break;
+                }
+                (phi, a) = (phi_next, a_next);
+            }
+        }
+        Ok(agent)
+    }
+}
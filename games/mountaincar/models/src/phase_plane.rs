@@ -0,0 +1,102 @@
+//! Action values of an agent over the phase plane of Mountain Car, the plane of the positions
+//! and the speeds of the car.
+use mountaincar_env::{Ground, MountainCar};
+use rl::ai::{greedy_index, QFunction};
+use rl::mdp::MarkovDecisionProcess;
+use std::error::Error;
+
+/// Action values at the centers of a regular grid of the phase plane, spanning the observation
+/// space of the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QGrid {
+    /// Number of cells along the position and along the speed.
+    pub shape: (usize, usize),
+
+    /// Lowest position and speed of the grid.
+    pub low: (f32, f32),
+
+    /// Highest position and speed of the grid.
+    pub high: (f32, f32),
+
+    /// Values of the actions in each cell, the position varying fastest.
+    pub q_values: Vec<Vec<f32>>,
+}
+
+impl QGrid {
+    /// Query the action values of the agent at the center of each cell, the car of the probe
+    /// being moved there.
+    pub fn compute<G, A>(
+        agent: &A,
+        probe: &mut MountainCar<G>,
+        shape: (usize, usize),
+    ) -> Result<Self, Box<dyn Error>>
+    where
+        G: Ground,
+        A: QFunction<MountainCar<G>> + ?Sized,
+    {
+        let space = probe.observation_space();
+        let mut grid = QGrid {
+            shape,
+            low: (space.low[0], space.low[1]),
+            high: (space.high[0], space.high[1]),
+            q_values: Vec::with_capacity(shape.0 * shape.1),
+        };
+        for j in 0..shape.1 {
+            for i in 0..shape.0 {
+                (probe.pos, probe.speed) = grid.cell_center(i, j);
+                grid.q_values.push(agent.q_values(probe)?);
+            }
+        }
+        Ok(grid)
+    }
+
+    /// Position and speed at the center of the cell.
+    pub fn cell_center(&self, i: usize, j: usize) -> (f32, f32) {
+        let (x, y) = (
+            (i as f32 + 0.5) / self.shape.0 as f32,
+            (j as f32 + 0.5) / self.shape.1 as f32,
+        );
+        (
+            self.low.0 + x * (self.high.0 - self.low.0),
+            self.low.1 + y * (self.high.1 - self.low.1),
+        )
+    }
+
+    /// Fractions of the grid covered along the position and along the speed to reach the state,
+    /// `(0, 0)` being the lowest corner and `(1, 1)` the highest one. States outside the grid
+    /// give fractions outside `[0, 1]`.
+    pub fn to_unit(&self, pos: f32, speed: f32) -> (f32, f32) {
+        (
+            (pos - self.low.0) / (self.high.0 - self.low.0),
+            (speed - self.low.1) / (self.high.1 - self.low.1),
+        )
+    }
+
+    /// Values of the actions in the cell.
+    pub fn q(&self, i: usize, j: usize) -> &[f32] {
+        &self.q_values[j * self.shape.0 + i]
+    }
+
+    /// Index of the action of highest value in the cell, the first one on ties.
+    pub fn greedy(&self, i: usize, j: usize) -> usize {
+        greedy_index(self.q(i, j))
+    }
+
+    /// Value of the best action in the cell.
+    pub fn value(&self, i: usize, j: usize) -> f32 {
+        self.q(i, j)
+            .iter()
+            .copied()
+            .fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    /// Lowest and highest values of the cells.
+    pub fn value_range(&self) -> (f32, f32) {
+        (0..self.shape.1)
+            .flat_map(|j| (0..self.shape.0).map(move |i| (i, j)))
+            .map(|(i, j)| self.value(i, j))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            })
+    }
+}
@@ -0,0 +1,64 @@
+//! Drawing of Mountain Car for the headless recordings, on the road of the renderer.
+use mountaincar_env::{MountainCar, RockyRoad};
+use recorder::{Canvas, Rgba, Scene, Vec2};
+
+/// Width of the window of the renderer, which the road spans.
+pub const WIDTH: f32 = 1620.0;
+
+/// Height of the window of the renderer.
+pub const HEIGHT: f32 = 1080.0;
+
+const SKY: Rgba<u8> = Rgba([170, 210, 240, 255]);
+const GROUND: Rgba<u8> = Rgba([110, 100, 90, 255]);
+const POLE: Rgba<u8> = Rgba([60, 60, 60, 255]);
+const FLAG: Rgba<u8> = Rgba([220, 40, 40, 255]);
+const BODY: Rgba<u8> = Rgba([255, 99, 71, 255]);
+const WINDOW: Rgba<u8> = Rgba([200, 230, 255, 255]);
+const WHEEL: Rgba<u8> = Rgba([30, 30, 30, 255]);
+
+/// Road, flag and car drawn with plain shapes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MountainCarScene;
+
+impl Scene<MountainCar<RockyRoad>> for MountainCarScene {
+    fn draw(&self, e: &MountainCar<RockyRoad>, canvas: &mut Canvas) {
+        canvas.clear(SKY);
+        let curve = &e.ground.0;
+
+        // The road closed by the bottom of the window
+        let mut ground: Vec<Vec2> = curve.iter_positions(80).collect();
+        if let (Some(first), Some(last)) = (ground.first().copied(), ground.last().copied()) {
+            ground.push(Vec2::new(last.x, -HEIGHT / 2.0));
+            ground.push(Vec2::new(first.x, -HEIGHT / 2.0));
+        }
+        canvas.fill_polygon(&ground, GROUND);
+
+        let flag = curve.position(e.config.goal);
+        let top = flag + Vec2::new(0.0, 72.0);
+        canvas.line(flag, top, 4.0, POLE);
+        canvas.fill_polygon(
+            &[
+                top,
+                top - Vec2::new(0.0, 24.0),
+                top + Vec2::new(40.0, -12.0),
+            ],
+            FLAG,
+        );
+
+        // The car stands on the road, turned along its slope
+        let p = curve.position(e.pos);
+        let tangent = curve.velocity(e.pos).normalize_or_zero();
+        let normal = tangent.perp();
+        let angle = tangent.y.atan2(tangent.x);
+        canvas.fill_rect(p + 20.0 * normal, Vec2::new(64.0, 20.0), angle, BODY);
+        canvas.fill_rect(
+            p + 36.0 * normal - 4.0 * tangent,
+            Vec2::new(32.0, 14.0),
+            angle,
+            WINDOW,
+        );
+        for side in [-1.0, 1.0] {
+            canvas.fill_circle(p + 8.0 * normal + side * 20.0 * tangent, 8.0, WHEEL);
+        }
+    }
+}
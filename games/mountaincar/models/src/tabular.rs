@@ -1,53 +1,131 @@
-use candle_core::Tensor;
-use rl::ai::{Agent, FileLoader};
+use candle_core::{DType, Tensor};
+use rl::ai::{greedy_index, Agent, FileLoader, QFunction, ValueFunction};
+use rl::discretizer::Discretizer;
+use rl::error::{self, LoadError};
+use rl::space::DiscreteAction;
+use rl::trainer::tabular::QTable;
 use std::collections::HashMap;
 use std::convert::TryFrom;
 use std::error::Error;
-use std::ops::Add;
 
-use mountaincar_env::{self, Ground, MountainAction, MountainCar};
+use mountaincar_env::{Ground, MountainAction, MountainCar};
 
+/// Number of bins along the position and along the speed of the tables saved before the grid
+/// was stored in the files.
+pub const LEGACY_GRID_SIZE: usize = 10;
+
+/// Tabular agent playing Mountain Car. Besides the files of `rl::tabular::Tabular`, it loads the
+/// legacy files storing one `(10, 10)` table per action, `q_left`, `q_nothing` and `q_right`,
+/// indexed by the position bin and the reversed speed bin.
 pub struct Tabular {
-    q_left: Tensor,
-    q_nothing: Tensor,
-    q_right: Tensor,
+    /// Table and grid of the agent.
+    pub table: rl::tabular::Tabular,
+
+    /// The table comes from a legacy file, whose ties went to Left, then Right, then DoNothing.
+    /// The other tables break their ties as the trainer does, to the lowest action index.
+    pub legacy: bool,
+}
+
+impl Tabular {
+    /// Grid of the legacy files: bins of 0.05 along the position from 0 and of 0.03 along the
+    /// speed from -0.15.
+    pub fn legacy_discretizer() -> Discretizer {
+        Discretizer {
+            low: vec![0.0, -0.15],
+            high: vec![0.5, 0.15],
+            bins: vec![LEGACY_GRID_SIZE, LEGACY_GRID_SIZE],
+        }
+    }
+
+    // Gather the three legacy tables into one, un-reversing the speed bins
+    fn from_legacy(h: &mut HashMap<String, Tensor>) -> Result<Self, LoadError> {
+        let n = LEGACY_GRID_SIZE;
+        let reversed = Tensor::new(
+            (0..n as u32).rev().collect::<Vec<_>>(),
+            &candle_core::Device::Cpu,
+        )?;
+        let mut columns = Vec::with_capacity(3);
+        for name in ["q_left", "q_nothing", "q_right"] {
+            let q = error::take_tensor(h, name)?;
+            if q.dims() != [n, n] {
+                return Err(LoadError::ShapeMismatch {
+                    name: name.to_owned(),
+                    expected: vec![n, n],
+                    found: q.dims().to_vec(),
+                });
+            }
+            let q = q
+                .to_device(&candle_core::Device::Cpu)?
+                .to_dtype(DType::F32)?;
+            columns.push(q.index_select(&reversed, 1)?.reshape(n * n)?);
+        }
+        let q = QTable::from_tensor(&Tensor::stack(&columns, 1)?)?;
+        Ok(Tabular {
+            table: rl::tabular::Tabular::new(q, Self::legacy_discretizer())?,
+            legacy: true,
+        })
+    }
 }
 
+// Indices of Left, Right and DoNothing: the order in which the legacy tables broke the ties
+// between the actions
+const LEGACY_TIE_ORDER: [usize; 3] = [0, 2, 1];
+
 impl<T: Ground> Agent<MountainCar<T>> for Tabular {
     fn policy(&self, e: &MountainCar<T>) -> Result<MountainAction, Box<dyn Error>> {
-        let i = e.pos.div_euclid(0.05).clamp(0.0, 9.0) as usize;
-        let j = 9 - e.speed.add(0.15).div_euclid(0.03).clamp(0.0, 9.0) as usize;
-        let q_l = self.q_left.get(i)?.get(j)?.to_scalar::<f64>()?;
-        let q_n = self.q_nothing.get(i)?.get(j)?.to_scalar::<f64>()?;
-        let q_r = self.q_right.get(i)?.get(j)?.to_scalar::<f64>()?;
-        if q_l >= q_n && q_l >= q_r {
-            Ok(MountainAction::Left)
-        } else if q_r >= q_n && q_r >= q_l {
-            Ok(MountainAction::Right)
-        } else {
-            Ok(MountainAction::DoNothing)
+        if !self.legacy {
+            return self.table.policy(e);
         }
+        let q = self.table.q_values(e)?;
+        let values: Vec<f32> = LEGACY_TIE_ORDER.iter().map(|&a| q[a]).collect();
+        MountainAction::from_index(LEGACY_TIE_ORDER[greedy_index(&values)])
+            .ok_or_else(|| "No action for this index".into())
+    }
+}
+
+impl<T: Ground> QFunction<MountainCar<T>> for Tabular {
+    fn q_values(&self, e: &MountainCar<T>) -> Result<Vec<f32>, Box<dyn Error>> {
+        self.table.q_values(e)
+    }
+}
+
+impl<T: Ground> ValueFunction<MountainCar<T>> for Tabular {
+    fn value(&self, e: &MountainCar<T>) -> Result<f32, Box<dyn Error>> {
+        self.table.value(e)
     }
 }
 
 impl TryFrom<&mut HashMap<String, Tensor>> for Tabular {
-    type Error = &'static str;
+    type Error = LoadError;
 
     fn try_from(h: &mut HashMap<String, Tensor>) -> Result<Self, Self::Error> {
-        let Some(q1) = h.remove("q_left") else {
-            return Err("Q_left not in");
-        };
-        let Some(q2) = h.remove("q_nothing") else {
-            return Err("Q_nothing not in");
-        };
-        let Some(q3) = h.remove("q_right") else {
-            return Err("Q_right not in");
+        let tabular = if h.contains_key("q_left") {
+            error::check_model_kind(h, rl::tabular::MODEL_KIND)?;
+            Self::from_legacy(h)?
+        } else {
+            Tabular {
+                table: rl::tabular::Tabular::try_from(h)?,
+                legacy: false,
+            }
         };
-        Ok(Tabular {
-            q_left: q1,
-            q_nothing: q2,
-            q_right: q3,
-        })
+
+        // A grid over the position and the speed, and one column per action
+        let (q, discretizer) = (&tabular.table.q, &tabular.table.discretizer);
+        if discretizer.bins.len() != 2 {
+            return Err(LoadError::ShapeMismatch {
+                name: "discretizer.bins".to_owned(),
+                expected: vec![2],
+                found: vec![discretizer.bins.len()],
+            });
+        }
+        if q.nb_actions() != MountainAction::COUNT {
+            return Err(LoadError::ShapeMismatch {
+                name: "q_values".to_owned(),
+                expected: vec![q.nb_states(), MountainAction::COUNT],
+                found: vec![q.nb_states(), q.nb_actions()],
+            });
+        }
+        Ok(tabular)
     }
 }
 
@@ -0,0 +1,103 @@
+use candle_core::{safetensors, Device};
+use mountaincar_mods::linear::{FeatureMap, LinearAgent};
+use rl::error::LoadError;
+
+#[test]
+fn tilings_activate_one_tile_each() {
+    let features = FeatureMap::TileCoding {
+        low: vec![0.0, -0.15],
+        high: vec![1.77, 0.15],
+        tilings: 4,
+        tiles: 5,
+    };
+    let phi = features.features(&[0.9, 0.02]);
+    assert_eq!(phi.len(), 4);
+    for (k, (i, v)) in phi.into_iter().enumerate() {
+        assert_eq!(i / 36, k);
+        assert_eq!(v, 1.0);
+    }
+}
+
+#[test]
+fn saved_agent_is_reloaded() {
+    for features in [
+        FeatureMap::TileCoding {
+            low: vec![0.0, -0.15],
+            high: vec![1.77, 0.15],
+            tilings: 4,
+            tiles: 5,
+        },
+        FeatureMap::Rbf {
+            low: vec![0.0, -0.15],
+            high: vec![1.77, 0.15],
+            centers: 6,
+            width: 0.8,
+        },
+    ] {
+        let mut agent = LinearAgent::new(features, 3);
+        for (k, w) in agent.weights.iter_mut().enumerate() {
+            *w = (k as f32 * 0.37).sin();
+        }
+        let path = std::env::temp_dir().join("mountaincar_saved_linear_agent.safetensors");
+        agent.save(&path).unwrap();
+
+        let mut h = safetensors::load(&path, &Device::Cpu).unwrap();
+        let loaded = LinearAgent::try_from(&mut h).unwrap();
+        assert_eq!(loaded.features, agent.features);
+        assert_eq!(loaded.weights, agent.weights);
+        let phi = agent.features.features(&[0.7, -0.05]);
+        assert_eq!(loaded.greedy(&phi), agent.greedy(&phi));
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+#[test]
+fn far_states_keep_normalized_bumps() {
+    let features = FeatureMap::Rbf {
+        low: vec![0.0, -0.15],
+        high: vec![1.77, 0.15],
+        centers: 6,
+        width: 0.1,
+    };
+    let phi = features.features(&[1.0e3, 10.0]);
+    assert!(phi.iter().all(|(_, v)| v.is_finite()));
+    assert!((phi.iter().map(|(_, v)| v).sum::<f32>() - 1.0).abs() < 1e-6);
+
+    // The nearest center is the last one, at the highest position and speed
+    assert_eq!(phi[35].1, 1.0);
+}
+
+#[test]
+fn bounds_must_cover_the_position_and_the_speed() {
+    let features = FeatureMap::TileCoding {
+        low: vec![0.0],
+        high: vec![1.77],
+        tilings: 4,
+        tiles: 5,
+    };
+    let path = std::env::temp_dir().join("mountaincar_one_dimension_linear_agent.safetensors");
+    LinearAgent::new(features, 3).save(&path).unwrap();
+    let mut h = safetensors::load(&path, &Device::Cpu).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert!(LinearAgent::try_from(&mut h).is_err());
+}
+
+#[test]
+fn agents_need_one_row_of_weights_per_action() {
+    let features = FeatureMap::TileCoding {
+        low: vec![0.0, -0.15],
+        high: vec![1.77, 0.15],
+        tilings: 4,
+        tiles: 5,
+    };
+    let path = std::env::temp_dir().join("mountaincar_two_actions_linear_agent.safetensors");
+    LinearAgent::new(features, 2).save(&path).unwrap();
+    let mut h = safetensors::load(&path, &Device::Cpu).unwrap();
+    std::fs::remove_file(path).unwrap();
+    match LinearAgent::try_from(&mut h) {
+        Err(LoadError::ShapeMismatch {
+            expected, found, ..
+        }) => assert_eq!((expected, found), (vec![3, 144], vec![2, 144])),
+        _ => panic!("an agent with 2 actions is loaded"),
+    }
+}
@@ -0,0 +1,134 @@
+use candle_core::{Device, Tensor};
+use mountaincar_env::{MountainAction, MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::phase_plane::QGrid;
+use mountaincar_mods::tabular::Tabular;
+use rl::ai::{Agent, FileLoader, QFunction, ValueFunction};
+use rl::discretizer::Discretizer;
+use rl::mdp::MarkovDecisionProcess;
+use rl::space::DiscreteAction;
+use rl::trainer::tabular::TabularTrainer;
+use std::collections::HashMap;
+
+// Cell of the legacy grid, with the reversed speed bins
+fn legacy_cell(pos: f32, speed: f32) -> (usize, usize) {
+    let i = pos.div_euclid(0.05).clamp(0.0, 9.0) as usize;
+    let j = 9 - (speed + 0.15).div_euclid(0.03).clamp(0.0, 9.0) as usize;
+    (i, j)
+}
+
+// Legacy tables whose action values encode the legacy cell and the action
+fn legacy_tables() -> HashMap<String, Tensor> {
+    let table = |a: f64| {
+        let values: Vec<f64> = (0..100).map(|k| k as f64 + a / 10.0).collect();
+        Tensor::from_vec(values, (10, 10), &Device::Cpu).unwrap()
+    };
+    HashMap::from([
+        ("q_left".to_owned(), table(0.0)),
+        ("q_nothing".to_owned(), table(1.0)),
+        ("q_right".to_owned(), table(2.0)),
+    ])
+}
+
+#[test]
+fn legacy_tables_keep_their_cells() {
+    let tabular = Tabular::try_from(&mut legacy_tables()).unwrap().table;
+
+    for (pos, speed) in [(0.02, -0.14), (0.26, 0.01), (0.47, 0.13), (1.2, -0.3)] {
+        let (i, j) = legacy_cell(pos, speed);
+        let row = tabular.q.row(tabular.discretizer.index(&[pos, speed]));
+        let expected = (i * 10 + j) as f32;
+        assert_eq!(row, [expected, expected + 0.1, expected + 0.2]);
+    }
+}
+
+#[test]
+fn action_values_are_read_in_the_cell_of_the_car() {
+    let tabular = Tabular::try_from(&mut legacy_tables()).unwrap();
+    let mut car = MountainCar::new(RockyRoad::default(), MountainCarConfig::default()).unwrap();
+    car.pos = 0.26;
+    car.speed = 0.01;
+
+    let (i, j) = legacy_cell(car.pos, car.speed);
+    let expected = (i * 10 + j) as f32;
+    assert_eq!(
+        tabular.q_values(&car).unwrap(),
+        [expected, expected + 0.1, expected + 0.2]
+    );
+    assert_eq!(tabular.value(&car).unwrap(), expected + 0.2);
+    assert_eq!(tabular.policy(&car).unwrap(), MountainAction::Right);
+}
+
+#[test]
+fn ties_go_to_left_then_right() {
+    // Legacy tables with the same value everywhere for each action
+    let policy = |left: f64, nothing: f64, right: f64| {
+        let table = |q| Tensor::full(q, (10, 10), &Device::Cpu).unwrap();
+        let mut tables = HashMap::from([
+            ("q_left".to_owned(), table(left)),
+            ("q_nothing".to_owned(), table(nothing)),
+            ("q_right".to_owned(), table(right)),
+        ]);
+        let tabular = Tabular::try_from(&mut tables).unwrap();
+        let car = MountainCar::new(RockyRoad::default(), MountainCarConfig::default()).unwrap();
+        tabular.policy(&car).unwrap()
+    };
+    assert_eq!(policy(-1.0, 0.0, 0.0), MountainAction::Right);
+    assert_eq!(policy(0.0, -1.0, 0.0), MountainAction::Left);
+    assert_eq!(policy(0.0, 0.0, 0.0), MountainAction::Left);
+}
+
+#[test]
+fn trained_tables_keep_their_ties_once_reloaded() {
+    // Greedy from zero values: Left is tried first and loses, leaving DoNothing and Right tied
+    let trainer = TabularTrainer {
+        epsilon: 0.0,
+        nb_episodes: 1,
+        max_steps: 50,
+        seed: Some(0),
+        ..Default::default()
+    };
+    let mut car = MountainCar::new(RockyRoad::default(), MountainCarConfig::default()).unwrap();
+    let discretizer = Discretizer::from_space(&car.observation_space(), vec![10, 10]).unwrap();
+    let q = trainer
+        .train(&mut car, discretizer.nb_states(), |x| {
+            Ok(discretizer.index_tensor(x)?)
+        })
+        .unwrap();
+    let path = std::env::temp_dir().join("mountaincar_trained_tabular.safetensors");
+    rl::tabular::Tabular::new(q.clone(), discretizer.clone())
+        .unwrap()
+        .save(&path)
+        .unwrap();
+    let tabular: Tabular = FileLoader::<MountainCar<RockyRoad>>::from_file(path.clone()).unwrap();
+    std::fs::remove_file(path).unwrap();
+
+    let mut ties = 0;
+    for (pos, speed) in (0..20).flat_map(|i| (0..20).map(move |j| (i, j))) {
+        car.pos = pos as f32 * 0.09;
+        car.speed = -0.15 + speed as f32 * 0.015;
+        let s = discretizer.index(&[car.pos, car.speed]);
+        let row = q.row(s);
+        ties += (row[1] == row[2] && row[0] < row[1]) as usize;
+        assert_eq!(tabular.policy(&car).unwrap().index(), q.greedy(s));
+    }
+    assert!(ties > 0);
+}
+
+#[test]
+fn phase_plane_holds_the_values_of_the_cells() {
+    let tabular = Tabular::try_from(&mut legacy_tables()).unwrap();
+    let mut probe = MountainCar::new(RockyRoad::default(), MountainCarConfig::default()).unwrap();
+    let grid = QGrid::compute(&tabular, &mut probe, (40, 10)).unwrap();
+    assert_eq!(grid.q_values.len(), 400);
+
+    // Lowest corner: first position bin and lowest speed bin, reversed
+    let (pos, speed) = grid.cell_center(0, 0);
+    assert_eq!(legacy_cell(pos, speed), (0, 9));
+    assert_eq!(grid.q(0, 0), [9.0, 9.1, 9.2]);
+    assert_eq!((grid.greedy(0, 0), grid.value(0, 0)), (2, 9.2));
+    let (x, y) = grid.to_unit(pos, speed);
+    assert!((x - 0.5 / 40.0).abs() < 1e-6 && (y - 0.05).abs() < 1e-6);
+
+    // Beyond the legacy grid, the positions fall in its last bin
+    assert_eq!(grid.value_range(), (0.2, 99.2));
+}
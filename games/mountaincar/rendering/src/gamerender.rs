@@ -1,46 +1,50 @@
 use crate::resources::*;
-use crate::wrapper_bezier::{CubicTransform, RockyRoad, TriangleStrip, Wrapper};
+use crate::wrapper_bezier::{CubicTransform, TriangleStrip, Wrapper};
 use crate::HEIGHT;
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
-use mountaincar_env::{MountainAction, MountainCar};
+use mountaincar_env::{MountainAction, MountainCar, RockyRoad};
 use rl::mdp::MarkovDecisionProcess;
-use uilib::{despawn_screen, remove_brain, AIResource, GameMode, GameState};
+use uilib::{
+    despawn_screen, remove_brain, save_trajectories, AIMode, AIResource, GameMode, GameState,
+    MenuMessage, RecordDemonstrations, Replay, DEMONSTRATION_DIR, TRAJECTORY_DIR,
+};
 
 pub fn mountain_car_plugin(app: &mut App) {
-    app.init_resource::<BrainType>()
-        .add_systems(
-            OnEnter(GameState::Playing),
-            (
-                setup_resources,
-                setup_decor.after(setup_resources),
-                setup_text.after(setup_resources),
-            ),
-        )
-        .add_systems(OnEnter(GameMode::AI), load_brain)
-        .add_systems(
-            FixedUpdate,
-            (
-                play_human.run_if(in_state(GameMode::Human)),
-                play_ai
-                    .run_if(in_state(GameMode::AI))
-                    .run_if(resource_exists::<AIResource<MountainCar<RockyRoad>>>),
-                move_car,
-                timer_text_update_system,
-                state_text_update_system,
-                end_of_game,
-            )
-                .run_if(in_state(GameState::Playing)),
+    app.add_systems(
+        OnEnter(GameState::Playing),
+        (
+            setup_resources,
+            setup_decor.after(setup_resources),
+            setup_text.after(setup_resources),
+        ),
+    )
+    .add_systems(OnEnter(GameMode::AI), load_brain)
+    .add_systems(
+        FixedUpdate,
+        (
+            play_human.run_if(in_state(GameMode::Human)),
+            play_ai
+                .run_if(in_state(GameMode::AI))
+                .run_if(resource_exists::<AIResource<MountainCar<RockyRoad>>>),
+            show_replay
+                .run_if(in_state(GameMode::Replay))
+                .run_if(resource_exists::<Replay>),
+            (move_car, timer_text_update_system, state_text_update_system).after(show_replay),
+            end_of_game.run_if(not(in_state(GameMode::Replay))),
         )
-        .add_systems(
-            OnExit(GameState::Playing),
-            (
-                despawn_screen::<StateText>,
-                despawn_screen::<TimeText>,
-                despawn_screen::<Car>,
-                despawn_screen::<Decor>,
-                remove_brain::<MountainCar<RockyRoad>>.run_if(in_state(GameMode::AI)),
-            ),
-        );
+            .run_if(in_state(GameState::Playing)),
+    )
+    .add_systems(
+        OnExit(GameState::Playing),
+        (
+            despawn_screen::<StateText>,
+            despawn_screen::<TimeText>,
+            despawn_screen::<Car>,
+            despawn_screen::<Decor>,
+            save_game.run_if(not(in_state(GameMode::Replay))),
+            remove_brain::<MountainCar<RockyRoad>>.run_if(in_state(GameMode::AI)),
+        ),
+    );
 }
 
 // A unit struct to help identify the timer UI component, since there may be many Text components
@@ -110,12 +114,7 @@ fn setup_decor(
     ));
 }
 
-fn setup_text(
-    mut commands: Commands,
-    asset_server: Res<AssetServer>,
-    mut wrap: ResMut<Wrapper>,
-    mut timer: ResMut<GameTimer>,
-) {
+fn setup_text(mut commands: Commands, asset_server: Res<AssetServer>, mut wrap: ResMut<Wrapper>) {
     // Spawn score text
     // Spawn the car
     wrap.m.reset();
@@ -195,9 +194,6 @@ fn setup_text(
         }),
         StateText,
     ));
-
-    // Reset timer
-    timer.0.reset();
 }
 
 fn play_human(
@@ -214,9 +210,9 @@ fn play_human(
             MountainAction::DoNothing
         }
     };
-    wrap.m
-        .step(action, time_step.timestep().as_secs_f32())
-        .unwrap_or(0.0);
+    if let Err(e) = wrap.m.step(action, time_step.timestep().as_secs_f32()) {
+        error!("The game could not step forward: {e}");
+    }
 }
 
 fn move_car(mut query: Query<&mut Transform, With<Car>>, wrap: Res<Wrapper>) {
@@ -227,20 +223,48 @@ fn move_car(mut query: Query<&mut Transform, With<Car>>, wrap: Res<Wrapper>) {
 fn play_ai(
     mut wrap: ResMut<Wrapper>,
     time_step: Res<Time<Fixed>>,
-    brain: Res<AIResource<MountainCar<RockyRoad>>>,
+    mut brain: ResMut<AIResource<MountainCar<RockyRoad>>>,
+    mode: Res<AIMode>,
 ) {
-    let action = brain.nn.policy(&wrap.m).unwrap_or_else(|_| {
+    let action = brain.act(&wrap.m, mode.0).unwrap_or_else(|_| {
         error!("AI brain could not compute the action to take!");
         MountainAction::DoNothing
     });
-    wrap.m
-        .step(action, time_step.timestep().as_secs_f32())
-        .unwrap_or(0.0);
+    if let Err(e) = wrap.m.step(action, time_step.timestep().as_secs_f32()) {
+        error!("The game could not step forward: {e}");
+    }
 }
 
-fn timer_text_update_system(mut query: Query<&mut Text, With<TimeText>>, timer: Res<GameTimer>) {
+// Put the car where it is at the current time of the replay
+fn show_replay(
+    mut wrap: ResMut<Wrapper>,
+    replay: Res<Replay>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let &[pos, speed] = replay.feature() else {
+        error!("The replayed game is not a game of Mountain Car.");
+        commands.insert_resource(MenuMessage(
+            "The replayed game is not a game of Mountain Car.".to_owned(),
+        ));
+        game_state.set(GameState::Menu);
+        return;
+    };
+    wrap.m.pos = pos;
+    wrap.m.speed = speed;
+}
+
+fn timer_text_update_system(
+    mut query: Query<&mut Text, With<TimeText>>,
+    wrap: Res<Wrapper>,
+    time_step: Res<Time<Fixed>>,
+    replay: Option<Res<Replay>>,
+) {
     for mut text in &mut query {
-        let t = timer.0.remaining_secs();
+        let t = match &replay {
+            Some(r) => r.duration() - r.time,
+            None => wrap.m.remaining_steps() as f32 * time_step.timestep().as_secs_f32(),
+        };
         text.sections[1].value = format!("{t:.1}")
     }
 }
@@ -253,14 +277,22 @@ fn state_text_update_system(mut query: Query<&mut Text, With<StateText>>, wrap:
     }
 }
 
-// Tick the timer, and change state when finished
-fn end_of_game(
-    mut timer: ResMut<GameTimer>,
-    time: Res<Time>,
-    mut game_state: ResMut<NextState<GameState>>,
+// Save the game, and keep it as a demonstration if asked and played by a human
+fn save_game(
     wrap: Res<Wrapper>,
+    game_mode: Res<State<GameMode>>,
+    demonstrations: Res<RecordDemonstrations>,
 ) {
-    if timer.0.tick(time.delta()).just_finished() || wrap.m.is_finished() {
+    let trajectories = wrap.m.trajectories();
+    save_trajectories(TRAJECTORY_DIR, "mountaincar", &trajectories);
+    if demonstrations.0 && *game_mode.get() == GameMode::Human {
+        save_trajectories(DEMONSTRATION_DIR, "mountaincar", &trajectories);
+    }
+}
+
+// Change state when the car reaches the flag or the time is over
+fn end_of_game(mut game_state: ResMut<NextState<GameState>>, wrap: Res<Wrapper>) {
+    if wrap.m.is_finished() || wrap.m.is_truncated() {
         game_state.set(GameState::Menu);
     }
 }
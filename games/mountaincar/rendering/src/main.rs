@@ -1,16 +1,19 @@
 use std::path::Path;
 
 use bevy::prelude::*;
-use uilib::{default_plugin, ButtonColors, Customization, MenuPlugin, SplashPlugin};
+// Window size, shared with the headless recordings
+use mountaincar_mods::scene::{HEIGHT, WIDTH};
+use uilib::{
+    default_plugin, ButtonColors, Customization, HudAxis, HudChart, HudPlugin, MenuPlugin,
+    SplashPlugin,
+};
+use wrapper_bezier::Wrapper;
 
 mod gamerender;
+mod overlay;
 mod resources;
 mod wrapper_bezier;
 
-// Window size
-const HEIGHT: f32 = 1080.0;
-const WIDTH: f32 = 1620.0;
-
 fn main() {
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
@@ -46,6 +49,18 @@ fn main() {
             },
             // Main game rendering
             gamerender::mountain_car_plugin,
+            // Action values of the AI over the phase plane
+            overlay::overlay_plugin,
+            // Charts of the last seconds of the game
+            HudPlugin::<Wrapper>::new(vec![
+                HudChart::over_time("Speed", HudAxis::Feature(1)),
+                HudChart {
+                    title: "Speed against position",
+                    x: HudAxis::Feature(0),
+                    y: HudAxis::Feature(1),
+                },
+                HudChart::over_time("Cumulative reward", HudAxis::Reward),
+            ]),
         ))
         .run()
 }
@@ -0,0 +1,226 @@
+use crate::wrapper_bezier::Wrapper;
+use crate::{HEIGHT, WIDTH};
+use bevy::prelude::*;
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::render::texture::ImageSampler;
+use mountaincar_mods::phase_plane::QGrid;
+use uilib::{despawn_screen, GameMode, GameState};
+
+// Size and center of the panel, in the top right corner of the window
+const PANEL_SIZE: Vec2 = Vec2::new(480.0, 320.0);
+const PANEL_CENTER: Vec2 = Vec2::new(WIDTH / 2.0 - 270.0, HEIGHT / 2.0 - 210.0);
+
+// Colors of the greedy actions: left, nothing and right
+const ACTION_COLORS: [[f32; 3]; 3] = [[0.2, 0.4, 0.9], [0.6, 0.6, 0.6], [0.9, 0.3, 0.2]];
+
+// Colors of the lowest, middle and highest values
+const VALUE_COLORS: [[f32; 3]; 3] = [[0.05, 0.05, 0.3], [0.1, 0.6, 0.6], [1.0, 0.9, 0.2]];
+
+/// Action values of the AI over the phase plane, queried when its brain is loaded.
+#[derive(Resource)]
+pub struct PhasePlane(pub QGrid);
+
+/// Way the phase plane is colored.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum OverlayDisplay {
+    /// Color of the greedy action, brighter where its value is higher.
+    #[default]
+    Policy,
+
+    /// Value of the greedy action.
+    Values,
+}
+
+/// State of the overlay, kept from one game to the next.
+#[derive(Resource, Debug, Default)]
+pub struct Overlay {
+    /// The panel is displayed.
+    pub visible: bool,
+
+    /// Way the phase plane is colored.
+    pub display: OverlayDisplay,
+}
+
+#[derive(Component)]
+struct OverlayPanel;
+
+#[derive(Component)]
+struct OverlayTitle;
+
+// The overlay is available while the AI plays: V shows and hides it, C switches its colors
+pub fn overlay_plugin(app: &mut App) {
+    app.init_resource::<Overlay>()
+        .add_systems(
+            Update,
+            (
+                spawn_overlay.run_if(resource_added::<PhasePlane>),
+                control_overlay,
+                draw_trajectory,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(GameMode::AI))
+                .run_if(resource_exists::<PhasePlane>),
+        )
+        .add_systems(
+            OnExit(GameState::Playing),
+            (despawn_screen::<OverlayPanel>, remove_phase_plane),
+        );
+}
+
+fn title(display: OverlayDisplay) -> &'static str {
+    match display {
+        OverlayDisplay::Policy => "Greedy action: blue left, grey nothing, red right (V, C)",
+        OverlayDisplay::Values => "Value of the greedy action: dark low, bright high (V, C)",
+    }
+}
+
+fn visibility(visible: bool) -> Visibility {
+    if visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+// Cells of the grid as pixels, the speeds increasing upwards
+fn heatmap(grid: &QGrid, display: OverlayDisplay) -> Image {
+    let (nx, ny) = grid.shape;
+    let (lo, hi) = grid.value_range();
+    let mut data = Vec::with_capacity(4 * nx * ny);
+    for j in (0..ny).rev() {
+        for i in 0..nx {
+            let t = if hi > lo {
+                (grid.value(i, j) - lo) / (hi - lo)
+            } else {
+                1.0
+            };
+            let rgb = match display {
+                OverlayDisplay::Policy => {
+                    ACTION_COLORS[grid.greedy(i, j).min(2)].map(|c| c * (0.4 + 0.6 * t))
+                }
+                OverlayDisplay::Values => {
+                    let (from, to, s) = if t < 0.5 {
+                        (VALUE_COLORS[0], VALUE_COLORS[1], 2.0 * t)
+                    } else {
+                        (VALUE_COLORS[1], VALUE_COLORS[2], 2.0 * t - 1.0)
+                    };
+                    [0, 1, 2].map(|k| from[k] + s * (to[k] - from[k]))
+                }
+            };
+            data.extend(rgb.map(|c| (255.0 * c.clamp(0.0, 1.0)) as u8));
+            data.push(255);
+        }
+    }
+    let mut image = Image::new(
+        Extent3d {
+            width: nx as u32,
+            height: ny as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        data,
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::RENDER_WORLD | RenderAssetUsages::MAIN_WORLD,
+    );
+    image.sampler = ImageSampler::nearest();
+    image
+}
+
+fn spawn_overlay(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    plane: Res<PhasePlane>,
+    overlay: Res<Overlay>,
+) {
+    commands
+        .spawn((
+            SpriteBundle {
+                texture: images.add(heatmap(&plane.0, overlay.display)),
+                sprite: Sprite {
+                    custom_size: Some(PANEL_SIZE),
+                    ..default()
+                },
+                transform: Transform::from_translation(PANEL_CENTER.extend(5.0)),
+                visibility: visibility(overlay.visible),
+                ..default()
+            },
+            OverlayPanel,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text2dBundle {
+                    text: Text::from_section(
+                        title(overlay.display),
+                        TextStyle {
+                            font_size: 16.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, PANEL_SIZE.y / 2.0 + 14.0, 0.0),
+                    ..default()
+                },
+                OverlayTitle,
+            ));
+        });
+}
+
+fn control_overlay(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut overlay: ResMut<Overlay>,
+    plane: Res<PhasePlane>,
+    mut images: ResMut<Assets<Image>>,
+    mut panels: Query<(&Handle<Image>, &mut Visibility), With<OverlayPanel>>,
+    mut titles: Query<&mut Text, With<OverlayTitle>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyV) {
+        overlay.visible = !overlay.visible;
+        for (_, mut v) in &mut panels {
+            *v = visibility(overlay.visible);
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::KeyC) {
+        overlay.display = match overlay.display {
+            OverlayDisplay::Policy => OverlayDisplay::Values,
+            OverlayDisplay::Values => OverlayDisplay::Policy,
+        };
+        for (handle, _) in &panels {
+            images.insert(handle, heatmap(&plane.0, overlay.display));
+        }
+        for mut text in &mut titles {
+            text.sections[0].value = title(overlay.display).to_owned();
+        }
+    }
+}
+
+// Trace the states met since the start of the game and mark the current one
+fn draw_trajectory(
+    mut gizmos: Gizmos,
+    overlay: Res<Overlay>,
+    plane: Res<PhasePlane>,
+    wrap: Res<Wrapper>,
+) {
+    if !overlay.visible {
+        return;
+    }
+    let to_panel = |pos: f32, speed: f32| {
+        let (x, y) = plane.0.to_unit(pos, speed);
+        PANEL_CENTER + (Vec2::new(x, y).clamp(Vec2::ZERO, Vec2::ONE) - 0.5) * PANEL_SIZE
+    };
+    gizmos.rect_2d(PANEL_CENTER, 0.0, PANEL_SIZE, Color::BLACK);
+    let t = wrap.m.current_trajectory();
+    gizmos.linestrip_2d(
+        (0..=t.len()).map(|i| match t.feature(i) {
+            &[pos, speed, ..] => to_panel(pos, speed),
+            _ => PANEL_CENTER,
+        }),
+        Color::WHITE,
+    );
+    gizmos.circle_2d(to_panel(wrap.m.pos, wrap.m.speed), 6.0, Color::BLACK);
+}
+
+fn remove_phase_plane(mut commands: Commands) {
+    commands.remove_resource::<PhasePlane>();
+}
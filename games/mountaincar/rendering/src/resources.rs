@@ -1,52 +1,52 @@
-use crate::wrapper_bezier::{RockyRoad, Wrapper};
-use crate::WIDTH;
-use bevy::{math::vec2, prelude::*};
-use mountaincar_env::MountainCar;
+use crate::overlay::PhasePlane;
+use crate::wrapper_bezier::Wrapper;
+use bevy::prelude::*;
+use mountaincar_env::{MountainCar, MountainCarConfig, RockyRoad};
+use mountaincar_mods::linear::{self, LinearAgent};
 use mountaincar_mods::mlp::MultiLayerPerceptron;
+use mountaincar_mods::phase_plane::QGrid;
 use mountaincar_mods::tabular::Tabular;
 use rfd::FileDialog;
-use rl::ai::FileLoader;
-use uilib::{AIResource, GameMode, GameState};
+use rl::ai::{FileLoader, QFunction};
+use rl::error;
+use rl::wrappers::{TimeLimit, TrajectoryRecorder};
+use std::error::Error;
+use std::path::PathBuf;
+use uilib::{AIResource, GameMode, GameState, MenuMessage};
 
-// Resource timer
-#[derive(Resource)]
-pub struct GameTimer(pub Timer);
+// Number of steps of a game: 30 seconds at 50 steps per second
+pub const GAME_STEPS: usize = 1500;
 
-#[derive(Resource, Default)]
-pub enum BrainType {
-    #[default]
-    Tab,
-    Mlp,
-}
-
-pub fn setup_resources(mut commands: Commands) {
-    let control_points = [
-        [
-            vec2(-WIDTH.div_euclid(2.0), -83.0), // 0.0
-            vec2(-77.0, -645.0),                 // -1120.0
-            vec2(311.0, -539.0),                 // 340.0
-            vec2(515.0, -326.0),                 // -30.0
-        ],
-        [
-            vec2(515.0, -326.0),                 // 0.0
-            vec2(703.0, -130.0),                 // -1120.0
-            vec2(714.0, -76.0),                  // 340.0
-            vec2(WIDTH.div_euclid(2.0), -133.0), // -30.0
-        ],
-    ];
+// Number of cells of the phase plane along the position and along the speed
+const PHASE_PLANE_SHAPE: (usize, usize) = (64, 48);
 
-    let bezier = CubicBezier::new(control_points).to_curve();
+type Game = MountainCar<RockyRoad>;
 
+pub fn setup_resources(mut commands: Commands) {
     commands.insert_resource(Wrapper {
-        m: MountainCar::new(RockyRoad(bezier)),
+        m: TrajectoryRecorder::new(TimeLimit::new(
+            MountainCar::new(RockyRoad::default(), MountainCarConfig::default())
+                .expect("The default configuration is valid"),
+            GAME_STEPS,
+        )),
     });
     commands.insert_resource(<Time<Fixed>>::from_seconds(1.0 / 50.0));
-    commands.insert_resource(GameTimer(Timer::from_seconds(30.0, TimerMode::Once)));
 }
 
+// Load the agent and query its action values over the phase plane, shown by the overlay
+fn load<A>(file: PathBuf) -> Result<(AIResource<Game>, QGrid), Box<dyn Error>>
+where
+    A: FileLoader<Game> + QFunction<Game> + Send + Sync + 'static,
+{
+    let agent = A::from_file(file)?;
+    let mut probe = MountainCar::new(RockyRoad::default(), MountainCarConfig::default())?;
+    let grid = QGrid::compute(&agent, &mut probe, PHASE_PLANE_SHAPE)?;
+    Ok((AIResource::new(Box::new(agent)), grid))
+}
+
+// The agent is picked from the kind of model named in the file
 pub fn load_brain(
     mut commands: Commands,
-    b: Res<BrainType>,
     mut game_state: ResMut<NextState<GameState>>,
     mut game_mode: ResMut<NextState<GameMode>>,
 ) {
@@ -61,19 +61,27 @@ pub fn load_brain(
         return;
     };
 
-    let nn: AIResource<MountainCar<RockyRoad>> = match *b {
-        BrainType::Tab => AIResource {
-            nn: Box::from(
-                <Tabular as FileLoader<MountainCar<RockyRoad>>>::from_file(file).unwrap(),
-            ),
-        },
-        BrainType::Mlp => AIResource {
-            nn: Box::from(
-                <MultiLayerPerceptron<2, 3> as FileLoader<MountainCar<RockyRoad>>>::from_file(file)
-                    .unwrap(),
-            ),
+    let nn = match error::model_kind(&file) {
+        Ok(kind) => match kind.as_deref() {
+            Some(rl::mlp::MODEL_KIND) => load::<MultiLayerPerceptron<2, 3>>(file),
+            Some(linear::MODEL_KIND) => load::<LinearAgent>(file),
+            // The legacy tables name no kind of model
+            Some(rl::tabular::MODEL_KIND) | None => load::<Tabular>(file),
+            Some(kind) => Err(format!("unknown kind of model {kind}").into()),
         },
+        Err(e) => Err(e.into()),
     };
 
-    commands.insert_resource(nn);
+    match nn {
+        Ok((nn, grid)) => {
+            commands.insert_resource(nn);
+            commands.insert_resource(PhasePlane(grid));
+        }
+        Err(e) => {
+            error!("The agent could not be loaded: {e}");
+            commands.insert_resource(MenuMessage(format!("The agent could not be loaded: {e}")));
+            game_state.set(GameState::Menu);
+            game_mode.set(GameMode::Human);
+        }
+    }
 }
@@ -2,26 +2,24 @@ use crate::{HEIGHT, WIDTH};
 use bevy::reflect::List;
 use bevy::render::render_asset::RenderAssetUsages;
 use bevy::{math::cubic_splines::CubicCurve, prelude::*, render::mesh::PrimitiveTopology};
-use mountaincar_env::{Ground, MountainCar};
+use mountaincar_env::{MountainCar, RockyRoad};
+use rl::wrappers::{TimeLimit, TrajectoryRecorder};
 use std::ops::{Add, Div};
-
-pub struct RockyRoad(pub CubicCurve<Vec2>);
+use uilib::HudSource;
 
 const PADDING: f32 = 13.0;
 
+// Every step of the game is recorded, to be replayed later
 #[derive(Resource)]
 pub struct Wrapper {
-    pub m: MountainCar<RockyRoad>,
+    pub m: TrajectoryRecorder<TimeLimit<MountainCar<RockyRoad>>>,
 }
 
-impl Ground for RockyRoad {
-    fn slope(&self, x: f32) -> f32 {
-        let v = self.0.velocity(x);
-        v.y / v.x
-    }
+impl HudSource for Wrapper {
+    type Game = TimeLimit<MountainCar<RockyRoad>>;
 
-    fn derivivative(&self, x: f32) -> f32 {
-        1_400.0 * self.0.velocity(x).length_recip()
+    fn recorder(&self) -> &TrajectoryRecorder<Self::Game> {
+        &self.m
     }
 }
 
@@ -2,8 +2,9 @@ use std::{convert::TryFrom, error::Error, f32::consts::PI};
 
 use bevy::math::Vec2;
 use candle_core::Tensor;
-use rand::Rng;
-use rl::mdp::MarkovDecisionProcess;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
 
 // "Ring Pong" decision process
 
@@ -11,10 +12,12 @@ pub const RADIUS: f32 = 300.0;
 pub const THETA: f32 = PI / 12.0;
 pub const SPEED: f32 = 100.0;
 
+#[derive(Clone)]
 pub struct RingPong {
     pub ball_pos: Vec2,
     pub ball_speed: Vec2,
     pub paddle_angle: f32,
+    rng: StdRng,
 }
 
 #[derive(Default, Debug, PartialEq)]
@@ -25,13 +28,36 @@ pub enum RingPongAction {
     DoNothing = 0,
 }
 
+impl DiscreteAction for RingPongAction {
+    const COUNT: usize = 3;
+
+    fn index(&self) -> usize {
+        match self {
+            RingPongAction::Left => 0,
+            RingPongAction::DoNothing => 1,
+            RingPongAction::Right => 2,
+        }
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        match i {
+            0 => Some(RingPongAction::Left),
+            1 => Some(RingPongAction::DoNothing),
+            2 => Some(RingPongAction::Right),
+            _ => None,
+        }
+    }
+}
+
 impl RingPong {
     pub fn new() -> Self {
-        let v_x = rand::thread_rng().gen_range(-1.0..1.0);
+        let mut rng = StdRng::from_entropy();
+        let v_x = rng.gen_range(-1.0..1.0);
         RingPong {
             ball_pos: Vec2::new(0.0, 0.0),
             ball_speed: Vec2::new(v_x, f32::sqrt(1.0 - v_x.powi(2))),
             paddle_angle: 0.0,
+            rng,
         }
     }
 }
@@ -42,6 +68,7 @@ impl Default for RingPong {
             ball_pos: Vec2::new(0.0, 0.0),
             ball_speed: Vec2::new(1.0, 0.0),
             paddle_angle: 0.0,
+            rng: StdRng::from_entropy(),
         }
     }
 }
@@ -50,21 +77,25 @@ impl MarkovDecisionProcess for RingPong {
     type Action = RingPongAction;
 
     fn reset(&mut self) {
-        let v_x = rand::thread_rng().gen_range(-1.0..1.0);
+        let v_x = self.rng.gen_range(-1.0..1.0);
         self.ball_pos = Vec2::new(0.0, 0.0);
         self.ball_speed = Vec2::new(v_x, f32::sqrt(1.0 - v_x.powi(2)));
         self.paddle_angle = 0.0;
     }
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
     fn is_finished(&self) -> bool {
         self.ball_pos.length() > RADIUS
     }
-    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<f32, Box<dyn Error>> {
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
         self.paddle_angle += time_step * action as i8 as f32;
         let pad_extremite_1 = RADIUS * Vec2::from_angle(self.paddle_angle - THETA);
         let pad_extremite_2 = RADIUS * Vec2::from_angle(self.paddle_angle + THETA);
         let t = (pad_extremite_1 - self.ball_pos).perp_dot(pad_extremite_2 - pad_extremite_1)
             / (SPEED * time_step * self.ball_speed).perp_dot(pad_extremite_2 - pad_extremite_1);
-        if t > 0.0 && t < 1.0 {
+        let bounce = t > 0.0 && t < 1.0;
+        if bounce {
             self.ball_pos += SPEED * t * time_step * self.ball_speed;
             self.ball_speed =
                 Vec2::from_angle(2.0 * self.paddle_angle + PI - self.ball_speed.to_angle());
@@ -72,7 +103,9 @@ impl MarkovDecisionProcess for RingPong {
         } else {
             self.ball_pos += SPEED * time_step * self.ball_speed;
         }
-        Ok(1.0) // Reward 1 at each step for surviving
+        // Reward 1 at each step for surviving
+        Ok(Transition::new(self.feature(), 1.0, self.is_finished())
+            .with_info("bounce", bounce as u8 as f32))
     }
 
     fn feature(&self) -> Tensor {
@@ -85,4 +118,12 @@ impl MarkovDecisionProcess for RingPong {
         ])
         .unwrap()
     }
+
+    fn observation_space(&self) -> BoxSpace {
+        // The paddle can turn around the ring as many times as it wants
+        BoxSpace::new(
+            vec![-RADIUS, -RADIUS, -1.0, -1.0, f32::NEG_INFINITY],
+            vec![RADIUS, RADIUS, 1.0, 1.0, f32::INFINITY],
+        )
+    }
 }
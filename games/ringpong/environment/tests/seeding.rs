@@ -0,0 +1,20 @@
+use ringpong_env::{RingPong, RingPongAction};
+use rl::mdp::MarkovDecisionProcess;
+
+fn trajectory(m: &mut RingPong, seed: u64) -> Vec<Vec<f32>> {
+    m.reset_with_seed(seed);
+    (0..100)
+        .map(|_| {
+            m.step(RingPongAction::Left, 0.1).unwrap();
+            m.feature().to_vec1().unwrap()
+        })
+        .collect()
+}
+
+#[test]
+fn identical_seeds_give_identical_trajectories() {
+    let first = trajectory(&mut RingPong::new(), 7);
+    let mut m = RingPong::default();
+    assert_eq!(first, trajectory(&mut m, 7));
+    assert_ne!(first, trajectory(&mut m, 8));
+}
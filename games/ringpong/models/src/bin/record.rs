@@ -0,0 +1,108 @@
+use clap::{Parser, ValueEnum};
+use recorder::{Canvas, RecordSettings, Rgba, Scene, Vec2};
+use ringpong_env::{RingPong, RADIUS, THETA};
+use rl::ai::{FileLoader, InferenceMode, ModalAgent};
+use rl::mlp::MultiLayerPerceptron;
+use std::error::Error;
+use std::f32::consts::PI;
+use std::path::PathBuf;
+
+const BACKGROUND: Rgba<u8> = Rgba([30, 30, 40, 255]);
+const RING: Rgba<u8> = Rgba([90, 90, 110, 255]);
+const PADDLE: Rgba<u8> = Rgba([77, 77, 179, 255]);
+const BALL: Rgba<u8> = Rgba([255, 128, 128, 255]);
+
+// Ring, paddle and ball drawn with the sizes of the renderer
+struct RingPongScene;
+
+impl Scene<RingPong> for RingPongScene {
+    fn draw(&self, e: &RingPong, canvas: &mut Canvas) {
+        canvas.clear(BACKGROUND);
+        canvas.fill_ring(Vec2::ZERO, RADIUS - 2.0, RADIUS + 2.0, RING);
+        canvas.fill_rect(
+            (RADIUS + 10.0) * Vec2::from_angle(e.paddle_angle),
+            Vec2::new(2.0 * RADIUS * THETA.sin(), 20.0),
+            e.paddle_angle + PI / 2.0,
+            PADDLE,
+        );
+        canvas.fill_circle(Vec2::new(e.ball_pos.x, e.ball_pos.y), 15.0, BALL);
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Mode {
+    Greedy,
+    Sampled,
+    EpsilonGreedy,
+}
+
+/// Record a game of Ring Pong played by a saved perceptron, without opening a window.
+#[derive(Parser)]
+struct Args {
+    /// Safetensors file storing the perceptron.
+    path: PathBuf,
+
+    /// Animated GIF written if the path ends with `.gif`, directory of numbered PNG frames
+    /// otherwise.
+    #[arg(short, long, default_value = "ringpong.gif")]
+    output: PathBuf,
+
+    /// Way the agent picks its actions.
+    #[arg(short, long, value_enum, default_value_t = Mode::Greedy)]
+    mode: Mode,
+
+    /// Probability of a random action in the epsilon-greedy mode.
+    #[arg(long, default_value_t = 0.05)]
+    epsilon: f32,
+
+    /// Size of the frames relative to the window of the game.
+    #[arg(long, default_value_t = 0.5)]
+    scale: f32,
+
+    /// Number of steps between two frames.
+    #[arg(long, default_value_t = 2)]
+    frame_every: usize,
+
+    /// Maximal number of steps of the game.
+    #[arg(long, default_value_t = 1_000)]
+    max_steps: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the game and of the stochastic modes.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let mode = match args.mode {
+        Mode::Greedy => InferenceMode::Greedy,
+        Mode::Sampled => InferenceMode::Sampled,
+        Mode::EpsilonGreedy => InferenceMode::EpsilonGreedy(args.epsilon),
+    };
+    let mlp = <MultiLayerPerceptron<5, 3> as FileLoader<RingPong>>::from_file(args.path)?;
+    let agent = ModalAgent::new(mlp, mode, args.seed);
+
+    // The ring and the paddle fill a square frame
+    let size = (2.0 * (RADIUS + 40.0) * args.scale).round() as u32;
+    let settings = RecordSettings {
+        width: size,
+        height: size,
+        scale: args.scale,
+        time_step: args.time_step,
+        max_steps: args.max_steps,
+        frame_every: args.frame_every,
+        seed: args.seed,
+    };
+    let recording = recorder::record(&agent, &mut RingPong::new(), &RingPongScene, &settings)?;
+    recording.save(&args.output)?;
+    println!(
+        "{} frames saved in {}",
+        recording.frames.len(),
+        args.output.display()
+    );
+    Ok(())
+}
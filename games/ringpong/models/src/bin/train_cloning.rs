@@ -0,0 +1,85 @@
+use clap::Parser;
+use rl::mlp::MultiLayerPerceptron;
+use rl::trainer::cloning::{accuracy, BehaviourCloningTrainer, Demonstrations};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Train a perceptron policy imitating recorded games of Ring Pong, e.g. the demonstrations
+/// of a human player, and save it in a safetensors file.
+#[derive(Parser)]
+struct Args {
+    /// Trajectory files, or directories of trajectory files, holding the demonstrations.
+    #[arg(required = true)]
+    demonstrations: Vec<PathBuf>,
+
+    /// File where the policy is saved.
+    #[arg(short, long, default_value = "cloning.safetensors")]
+    output: PathBuf,
+
+    /// Number of passes over the demonstrations.
+    #[arg(short = 'n', long, default_value_t = 50)]
+    epochs: usize,
+
+    /// Number of demonstrations per gradient step.
+    #[arg(long, default_value_t = 64)]
+    batch_size: usize,
+
+    /// Sizes of the internal layers of the policy.
+    #[arg(long, value_delimiter = ',', default_values_t = [32, 32])]
+    hidden_layers: Vec<usize>,
+
+    /// Learning rate of the policy.
+    #[arg(long, default_value_t = 1e-3)]
+    learning_rate: f64,
+
+    /// Weight the actions by the inverse of their frequency.
+    #[arg(long)]
+    balance_actions: bool,
+
+    /// Use the raw features instead of normalizing them with their statistics.
+    #[arg(long)]
+    raw_features: bool,
+
+    /// Fraction of the demonstrations kept aside to measure the accuracy of the policy.
+    #[arg(long, default_value_t = 0.1)]
+    validation: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let demonstrations = Demonstrations::load(&args.demonstrations)?;
+    println!(
+        "{} demonstrated actions (left, nothing, right): {:?}",
+        demonstrations.len(),
+        demonstrations.action_counts(3)
+    );
+    let (train, validation) = demonstrations.split(args.validation, args.seed);
+
+    let trainer = BehaviourCloningTrainer {
+        hidden_layers: args.hidden_layers,
+        learning_rate: args.learning_rate,
+        epochs: args.epochs,
+        batch_size: args.batch_size,
+        balance_actions: args.balance_actions,
+        normalize_observations: !args.raw_features,
+        seed: args.seed,
+    };
+    let policy: MultiLayerPerceptron<5, 3> = trainer.train(&train)?;
+    println!(
+        "Training accuracy:   {:.1}%",
+        100.0 * accuracy(&policy, &train)?
+    );
+    if !validation.is_empty() {
+        println!(
+            "Validation accuracy: {:.1}%",
+            100.0 * accuracy(&policy, &validation)?
+        );
+    }
+    policy.save(&args.output)?;
+    println!("Policy saved in {}", args.output.display());
+    Ok(())
+}
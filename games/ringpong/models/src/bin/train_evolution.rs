@@ -0,0 +1,116 @@
+use clap::{Parser, ValueEnum};
+use ringpong_env::RingPong;
+use rl::evaluation::EvaluationSettings;
+use rl::mdp::MarkovDecisionProcess;
+use rl::mlp::MultiLayerPerceptron;
+use rl::normalization::RunningMeanStd;
+use rl::trainer::black_box::{CemTrainer, EsTrainer};
+use std::error::Error;
+use std::path::PathBuf;
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Method {
+    /// Cross-entropy method.
+    Cem,
+    /// Evolution strategies.
+    Es,
+}
+
+/// Train a perceptron playing Ring Pong with a black-box optimizer and save it in a safetensors
+/// file. The positions of the ball are mapped to [-1, 1] with the radius of the ring.
+#[derive(Parser)]
+struct Args {
+    /// File where the perceptron is saved.
+    #[arg(short, long, default_value = "evolution.safetensors")]
+    output: PathBuf,
+
+    /// Optimizer of the parameters.
+    #[arg(short, long, value_enum, default_value_t = Method::Cem)]
+    method: Method,
+
+    /// Number of generations.
+    #[arg(short = 'n', long, default_value_t = 50)]
+    generations: usize,
+
+    /// Number of candidates evaluated at each generation.
+    #[arg(short, long, default_value_t = 50)]
+    population: usize,
+
+    /// Sizes of the internal layers of the perceptron.
+    #[arg(long, value_delimiter = ',', default_values_t = [16])]
+    hidden_layers: Vec<usize>,
+
+    /// Number of games played by each candidate.
+    #[arg(short, long, default_value_t = 4)]
+    games: u32,
+
+    /// Maximal number of steps of a game.
+    #[arg(long, default_value_t = 1_000)]
+    max_steps: usize,
+
+    /// Standard deviation of the first generation of the cross-entropy method, or of the
+    /// perturbations of the evolution strategies.
+    #[arg(long)]
+    std: Option<f32>,
+
+    /// Learning rate of the evolution strategies.
+    #[arg(long, default_value_t = 0.05)]
+    learning_rate: f32,
+
+    /// Number of threads evaluating the population. Defaults to the number of cores.
+    #[arg(long)]
+    threads: Option<usize>,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let env = RingPong::new();
+
+    let mut mlp = MultiLayerPerceptron::<5, 3>::random(&args.hidden_layers)?;
+    mlp.obs_norm = Some(RunningMeanStd::from_space(&env.observation_space()));
+    let evaluation = EvaluationSettings {
+        nb_games: args.games,
+        time_step: args.time_step,
+        max_steps: args.max_steps,
+        seed: None,
+    };
+
+    let mlp = match args.method {
+        Method::Cem => {
+            let mut trainer = CemTrainer {
+                population: args.population,
+                nb_generations: args.generations,
+                evaluation,
+                seed: args.seed,
+                ..Default::default()
+            };
+            trainer.initial_std = args.std.unwrap_or(trainer.initial_std);
+            trainer.nb_threads = args.threads.unwrap_or(trainer.nb_threads);
+            trainer.train(&env, &mlp)?
+        }
+        Method::Es => {
+            let mut trainer = EsTrainer {
+                population: args.population,
+                learning_rate: args.learning_rate,
+                nb_generations: args.generations,
+                evaluation,
+                seed: args.seed,
+                ..Default::default()
+            };
+            trainer.noise_std = args.std.unwrap_or(trainer.noise_std);
+            trainer.nb_threads = args.threads.unwrap_or(trainer.nb_threads);
+            trainer.train(&env, &mlp)?
+        }
+    };
+    mlp.save(&args.output)?;
+    println!("Perceptron saved in {}", args.output.display());
+    Ok(())
+}
@@ -0,0 +1,92 @@
+use clap::Parser;
+use ringpong_env::RingPong;
+use rl::trainer::ppo::{ActorCritic, PpoTrainer};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Train an actor and a critic playing Ring Pong with PPO and save them in safetensors files.
+/// The actor file is the one loaded by the game.
+#[derive(Parser)]
+struct Args {
+    /// File where the actor is saved, also used for the periodic checkpoints. The critic is saved
+    /// next to it.
+    #[arg(short, long, default_value = "ppo.safetensors")]
+    output: PathBuf,
+
+    /// Total number of steps played during the training.
+    #[arg(short = 'n', long, default_value_t = 1_000_000)]
+    steps: usize,
+
+    /// Number of copies of the game played together.
+    #[arg(long, default_value_t = 4)]
+    envs: usize,
+
+    /// Number of steps played by each game between two updates.
+    #[arg(long, default_value_t = 512)]
+    rollout_steps: usize,
+
+    /// Sizes of the internal layers of the actor and of the critic.
+    #[arg(long, value_delimiter = ',', default_values_t = [64, 64])]
+    hidden_layers: Vec<usize>,
+
+    /// Learning rate of the optimizer.
+    #[arg(long, default_value_t = 3e-4)]
+    learning_rate: f64,
+
+    /// Weight of the entropy bonus.
+    #[arg(long, default_value_t = 0.01)]
+    entropy_coef: f32,
+
+    /// Maximal number of steps of a training game.
+    #[arg(long, default_value_t = 1_000)]
+    max_steps: usize,
+
+    /// Use the raw features instead of normalizing them with their running mean and standard
+    /// deviation, which the distances in pixels need.
+    #[arg(long)]
+    raw_features: bool,
+
+    /// Start from the networks saved in the output files.
+    #[arg(long)]
+    resume: bool,
+
+    /// Number of updates between two checkpoints.
+    #[arg(long, default_value_t = 10)]
+    checkpoint_every: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = PpoTrainer {
+        hidden_layers: args.hidden_layers,
+        learning_rate: args.learning_rate,
+        rollout_steps: args.rollout_steps,
+        entropy_coef: args.entropy_coef,
+        total_steps: args.steps,
+        nb_envs: args.envs,
+        max_episode_steps: args.max_steps,
+        time_step: args.time_step,
+        normalize_observations: !args.raw_features,
+        checkpoint: Some(args.output.clone()),
+        checkpoint_every: args.checkpoint_every,
+        resume: args.resume,
+        seed: args.seed,
+        ..Default::default()
+    };
+
+    let _: ActorCritic<5, 3> = trainer.train(&RingPong::new())?;
+    println!(
+        "Actor saved in {} and critic in {}",
+        args.output.display(),
+        ActorCritic::<5, 3>::critic_path(&args.output).display()
+    );
+    Ok(())
+}
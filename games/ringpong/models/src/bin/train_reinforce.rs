@@ -0,0 +1,84 @@
+use clap::Parser;
+use ringpong_env::RingPong;
+use rl::mlp::MultiLayerPerceptron;
+use rl::trainer::reinforce::ReinforceTrainer;
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Train a perceptron policy playing Ring Pong with REINFORCE and save it in a safetensors
+/// file.
+#[derive(Parser)]
+struct Args {
+    /// File where the policy is saved, also used for the periodic checkpoints.
+    #[arg(short, long, default_value = "reinforce.safetensors")]
+    output: PathBuf,
+
+    /// Number of gradient steps.
+    #[arg(short = 'n', long, default_value_t = 1_000)]
+    updates: usize,
+
+    /// Number of games played for each gradient step.
+    #[arg(long, default_value_t = 8)]
+    batch_episodes: usize,
+
+    /// Sizes of the internal layers of the policy and of the baseline.
+    #[arg(long, value_delimiter = ',', default_values_t = [32, 32])]
+    hidden_layers: Vec<usize>,
+
+    /// Learning rate of the policy.
+    #[arg(long, default_value_t = 1e-3)]
+    learning_rate: f64,
+
+    /// Discount factor of the future rewards.
+    #[arg(long, default_value_t = 0.99)]
+    discount: f32,
+
+    /// Use the raw returns instead of subtracting a learned baseline.
+    #[arg(long)]
+    no_baseline: bool,
+
+    /// Maximal number of steps of a training game.
+    #[arg(long, default_value_t = 1_000)]
+    max_steps: usize,
+
+    /// Use the raw features instead of normalizing them with their running mean and standard
+    /// deviation, which the distances in pixels need.
+    #[arg(long)]
+    raw_features: bool,
+
+    /// Number of gradient steps between two checkpoints.
+    #[arg(long, default_value_t = 20)]
+    checkpoint_every: usize,
+
+    /// Time step of the simulation.
+    #[arg(short, long, default_value_t = 0.1)]
+    time_step: f32,
+
+    /// Seed of the training.
+    #[arg(short, long)]
+    seed: Option<u64>,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Args::parse();
+    let trainer = ReinforceTrainer {
+        hidden_layers: args.hidden_layers,
+        learning_rate: args.learning_rate,
+        baseline: !args.no_baseline,
+        discount: args.discount,
+        batch_episodes: args.batch_episodes,
+        nb_updates: args.updates,
+        max_episode_steps: args.max_steps,
+        time_step: args.time_step,
+        normalize_observations: !args.raw_features,
+        checkpoint: Some(args.output.clone()),
+        checkpoint_every: args.checkpoint_every,
+        seed: args.seed,
+        ..Default::default()
+    };
+
+    let mut env = RingPong::new();
+    let _: MultiLayerPerceptron<5, 3> = trainer.train(&mut env)?;
+    println!("Policy saved in {}", args.output.display());
+    Ok(())
+}
@@ -1,9 +1,16 @@
 use std::f32::consts::PI;
 
 use bevy::{prelude::*, sprite::MaterialMesh2dBundle};
+use rfd::FileDialog;
 use ringpong_env::{RingPong, RingPongAction, RADIUS, THETA};
+use rl::ai::FileLoader;
 use rl::mdp::MarkovDecisionProcess;
-use uilib::{despawn_screen, remove_brain, AIResource, GameMode, GameState};
+use rl::mlp::MultiLayerPerceptron;
+use rl::wrappers::{TimeLimit, TrajectoryRecorder};
+use uilib::{
+    despawn_screen, remove_brain, save_trajectories, AIMode, AIResource, GameMode, GameState,
+    HudSource, MenuMessage, RecordDemonstrations, Replay, DEMONSTRATION_DIR, TRAJECTORY_DIR,
+};
 
 // We set the z-value of the ball to 1 so it renders on top in the case of overlapping sprites.
 const BALL_DIAMETER: f32 = 30.;
@@ -30,13 +37,16 @@ pub fn mountain_car_plugin(app: &mut App) {
                 .run_if(in_state(GameState::Playing))
                 .run_if(in_state(GameMode::AI))
                 .run_if(resource_exists::<AIResource<RingPong>>),
-            (
-                move_paddle,
-                move_ball,
-                timer_text_update_system,
-                end_of_game,
-            )
+            show_replay
+                .run_if(in_state(GameState::Playing))
+                .run_if(in_state(GameMode::Replay))
+                .run_if(resource_exists::<Replay>),
+            (move_paddle, move_ball, timer_text_update_system)
+                .after(show_replay)
                 .run_if(in_state(GameState::Playing)),
+            end_of_game
+                .run_if(in_state(GameState::Playing))
+                .run_if(not(in_state(GameMode::Replay))),
         ),
     )
     .add_systems(
@@ -46,9 +56,11 @@ pub fn mountain_car_plugin(app: &mut App) {
             despawn_screen::<TimeText>,
             despawn_screen::<Paddle>,
             despawn_screen::<Ball>,
+            save_game.run_if(not(in_state(GameMode::Replay))),
             remove_brain::<RingPong>.run_if(in_state(GameMode::AI)),
         ),
-    );
+    )
+    .add_systems(OnEnter(GameMode::AI), load_brain);
 }
 
 // A unit struct to help identify the timer UI component, since there may be many Text components
@@ -64,18 +76,56 @@ struct Ball;
 #[derive(Component)]
 struct Paddle;
 
+// Every step of the game is recorded, to be replayed later
 #[derive(Resource)]
 pub struct Wrapper {
-    pub m: RingPong,
+    pub m: TrajectoryRecorder<TimeLimit<RingPong>>,
 }
 
-#[derive(Resource)]
-pub struct GameTimer(pub Timer);
+impl HudSource for Wrapper {
+    type Game = TimeLimit<RingPong>;
+
+    fn recorder(&self) -> &TrajectoryRecorder<Self::Game> {
+        &self.m
+    }
+}
+
+// Number of steps of a game: 30 seconds at 50 steps per second
+pub const GAME_STEPS: usize = 1500;
 
 pub fn setup_resources(mut commands: Commands) {
-    commands.insert_resource(Wrapper { m: RingPong::new() });
+    commands.insert_resource(Wrapper {
+        m: TrajectoryRecorder::new(TimeLimit::new(RingPong::new(), GAME_STEPS)),
+    });
     commands.insert_resource(<Time<Fixed>>::from_seconds(1.0 / 50.0));
-    commands.insert_resource(GameTimer(Timer::from_seconds(30.0, TimerMode::Once)));
+}
+
+// The brains are perceptrons reading the five features of the game
+fn load_brain(
+    mut commands: Commands,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<NextState<GameMode>>,
+) {
+    // Picking the file storing the brain
+    let Some(file) = FileDialog::new()
+        .add_filter("Safetensor file", &["safetensors"])
+        .pick_file()
+    else {
+        info!("No file picked. Return to main menu.");
+        game_state.set(GameState::Menu);
+        game_mode.set(GameMode::Human);
+        return;
+    };
+
+    match <MultiLayerPerceptron<5, 3> as FileLoader<RingPong>>::from_file(file) {
+        Ok(nn) => commands.insert_resource(AIResource::<RingPong>::new(Box::from(nn))),
+        Err(e) => {
+            error!("The agent could not be loaded: {e}");
+            commands.insert_resource(MenuMessage(format!("The agent could not be loaded: {e}")));
+            game_state.set(GameState::Menu);
+            game_mode.set(GameMode::Human);
+        }
+    }
 }
 
 fn setup_decor(
@@ -114,7 +164,7 @@ fn setup_decor(
     ));
 }
 
-fn setup_text(mut commands: Commands, mut wrap: ResMut<Wrapper>, mut timer: ResMut<GameTimer>) {
+fn setup_text(mut commands: Commands, mut wrap: ResMut<Wrapper>) {
     // Spawn score text
     wrap.m.reset();
 
@@ -141,9 +191,6 @@ fn setup_text(mut commands: Commands, mut wrap: ResMut<Wrapper>, mut timer: ResM
         }),
         TimeText,
     ));
-
-    // Reset timer
-    timer.0.reset();
 }
 
 fn update_mdp(
@@ -160,9 +207,9 @@ fn update_mdp(
             RingPongAction::DoNothing
         }
     };
-    wrap.m
-        .step(action, time_step.timestep().as_secs_f32())
-        .unwrap_or(0.0);
+    if let Err(e) = wrap.m.step(action, time_step.timestep().as_secs_f32()) {
+        error!("The game could not step forward: {e}");
+    }
 }
 
 fn move_ball(mut query_ball: Query<&mut Transform, With<Ball>>, wrap: Res<Wrapper>) {
@@ -179,33 +226,70 @@ fn move_paddle(mut query_paddle: Query<&mut Transform, With<Paddle>>, wrap: Res<
 fn update_mdp_ai(
     mut wrap: ResMut<Wrapper>,
     time_step: Res<Time<Fixed>>,
-    brain: Res<AIResource<RingPong>>,
+    mut brain: ResMut<AIResource<RingPong>>,
+    mode: Res<AIMode>,
 ) {
-    let action = brain.nn.policy(&wrap.m).unwrap_or_else(|_| {
+    let action = brain.act(&wrap.m, mode.0).unwrap_or_else(|_| {
         error!("AI brain could not compute the action to take!");
         RingPongAction::DoNothing
     });
 
-    wrap.m
-        .step(action, time_step.timestep().as_secs_f32())
-        .unwrap_or(0.0);
+    if let Err(e) = wrap.m.step(action, time_step.timestep().as_secs_f32()) {
+        error!("The game could not step forward: {e}");
+    }
+}
+
+// Put the ball and the paddle where they are at the current time of the replay
+fn show_replay(
+    mut wrap: ResMut<Wrapper>,
+    replay: Res<Replay>,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut commands: Commands,
+) {
+    let &[x, y, v_x, v_y, angle] = replay.feature() else {
+        error!("The replayed game is not a game of Ring Pong.");
+        commands.insert_resource(MenuMessage(
+            "The replayed game is not a game of Ring Pong.".to_owned(),
+        ));
+        game_state.set(GameState::Menu);
+        return;
+    };
+    wrap.m.ball_pos = Vec2::new(x, y);
+    wrap.m.ball_speed = Vec2::new(v_x, v_y);
+    wrap.m.paddle_angle = angle;
 }
 
-fn timer_text_update_system(mut query: Query<&mut Text, With<TimeText>>, timer: Res<GameTimer>) {
+fn timer_text_update_system(
+    mut query: Query<&mut Text, With<TimeText>>,
+    wrap: Res<Wrapper>,
+    time_step: Res<Time<Fixed>>,
+    replay: Option<Res<Replay>>,
+) {
     for mut text in &mut query {
-        let t = timer.0.remaining_secs();
+        let t = match &replay {
+            Some(r) => r.duration() - r.time,
+            None => wrap.m.remaining_steps() as f32 * time_step.timestep().as_secs_f32(),
+        };
         text.sections[1].value = format!("{t:.1}")
     }
 }
 
-// Tick the timer, and change state when finished
-fn end_of_game(
-    mut timer: ResMut<GameTimer>,
-    time: Res<Time>,
-    mut game_state: ResMut<NextState<GameState>>,
+// Save the game, and keep it as a demonstration if asked and played by a human
+fn save_game(
     wrap: Res<Wrapper>,
+    game_mode: Res<State<GameMode>>,
+    demonstrations: Res<RecordDemonstrations>,
 ) {
-    if timer.0.tick(time.delta()).just_finished() || wrap.m.is_finished() {
+    let trajectories = wrap.m.trajectories();
+    save_trajectories(TRAJECTORY_DIR, "ringpong", &trajectories);
+    if demonstrations.0 && *game_mode.get() == GameMode::Human {
+        save_trajectories(DEMONSTRATION_DIR, "ringpong", &trajectories);
+    }
+}
+
+// Change state when the ball is lost or the time is over
+fn end_of_game(mut game_state: ResMut<NextState<GameState>>, wrap: Res<Wrapper>) {
+    if wrap.m.is_finished() || wrap.m.is_truncated() {
         game_state.set(GameState::Menu);
     }
 }
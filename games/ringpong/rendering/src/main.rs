@@ -1,5 +1,9 @@
 use bevy::prelude::*;
-use uilib::{default_plugin, ButtonColors, Customization, MenuPlugin, SplashPlugin};
+use game_render::Wrapper;
+use uilib::{
+    default_plugin, ButtonColors, Customization, HudAxis, HudChart, HudPlugin, MenuPlugin,
+    SplashPlugin,
+};
 
 mod game_render;
 
@@ -51,6 +55,18 @@ fn main() {
             // Main game rendering
             //
             game_render::mountain_car_plugin,
+            //
+            // Charts of the last seconds of the game
+            //
+            HudPlugin::<Wrapper>::new(vec![
+                HudChart {
+                    title: "Ball position",
+                    x: HudAxis::Feature(0),
+                    y: HudAxis::Feature(1),
+                },
+                HudChart::over_time("Paddle angle", HudAxis::Feature(4)),
+                HudChart::over_time("Cumulative reward", HudAxis::Reward),
+            ]),
         ))
         .run()
 }
@@ -0,0 +1,123 @@
+//! Software rasterizer drawing plain shapes in the coordinates of the games.
+use bevy_math::Vec2;
+use image::{Rgba, RgbaImage};
+
+/// Image drawn in the coordinates of the games: the origin is at the center of the image, the
+/// `y` axis points up and one unit of the game is `scale` pixels.
+#[derive(Debug, Clone)]
+pub struct Canvas {
+    image: RgbaImage,
+    scale: f32,
+}
+
+impl Canvas {
+    /// Transparent canvas of the given size in pixels.
+    pub fn new(width: u32, height: u32, scale: f32) -> Self {
+        Canvas {
+            image: RgbaImage::new(width, height),
+            scale,
+        }
+    }
+
+    /// Image drawn so far.
+    pub fn image(&self) -> &RgbaImage {
+        &self.image
+    }
+
+    /// Take the image drawn.
+    pub fn into_image(self) -> RgbaImage {
+        self.image
+    }
+
+    /// Position in pixels of a point of the game.
+    pub fn to_pixel(&self, p: Vec2) -> Vec2 {
+        Vec2::new(
+            self.image.width() as f32 / 2.0 + p.x * self.scale,
+            self.image.height() as f32 / 2.0 - p.y * self.scale,
+        )
+    }
+
+    /// Paint the whole canvas.
+    pub fn clear(&mut self, color: Rgba<u8>) {
+        for p in self.image.pixels_mut() {
+            *p = color;
+        }
+    }
+
+    /// Fill a polygon given by its vertices, with the even-odd rule.
+    pub fn fill_polygon(&mut self, points: &[Vec2], color: Rgba<u8>) {
+        if points.len() < 3 {
+            return;
+        }
+        let points: Vec<Vec2> = points.iter().map(|&p| self.to_pixel(p)).collect();
+        let (width, height) = self.image.dimensions();
+        let top = points.iter().map(|p| p.y).fold(f32::INFINITY, f32::min);
+        let bottom = points.iter().map(|p| p.y).fold(f32::NEG_INFINITY, f32::max);
+        let first_row = top.floor().max(0.0) as u32;
+        let last_row = bottom.ceil().clamp(0.0, height as f32) as u32;
+
+        // Pixels whose center lies between two successive crossings of the edges are inside
+        let mut crossings = Vec::new();
+        for row in first_row..last_row {
+            let y = row as f32 + 0.5;
+            crossings.clear();
+            for (a, b) in points.iter().zip(points.iter().cycle().skip(1)) {
+                if (a.y <= y) != (b.y <= y) {
+                    crossings.push(a.x + (y - a.y) * (b.x - a.x) / (b.y - a.y));
+                }
+            }
+            crossings.sort_by(f32::total_cmp);
+            for pair in crossings.chunks_exact(2) {
+                let start = (pair[0] - 0.5).ceil().max(0.0) as u32;
+                let end = (pair[1] - 0.5).ceil().clamp(0.0, width as f32) as u32;
+                for x in start..end {
+                    self.image.put_pixel(x, row, color);
+                }
+            }
+        }
+    }
+
+    /// Fill a rectangle of the given size, centered on `center` and turned by `angle` radians.
+    pub fn fill_rect(&mut self, center: Vec2, size: Vec2, angle: f32, color: Rgba<u8>) {
+        let rotation = Vec2::from_angle(angle);
+        let corners = [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)]
+            .map(|(x, y)| center + rotation.rotate(Vec2::new(x, y) * size / 2.0));
+        self.fill_polygon(&corners, color);
+    }
+
+    /// Draw a segment of the given width.
+    pub fn line(&mut self, from: Vec2, to: Vec2, width: f32, color: Rgba<u8>) {
+        let d = to - from;
+        let angle = d.y.atan2(d.x);
+        self.fill_rect(
+            (from + to) / 2.0,
+            Vec2::new(d.length(), width),
+            angle,
+            color,
+        );
+    }
+
+    /// Fill a disc.
+    pub fn fill_circle(&mut self, center: Vec2, radius: f32, color: Rgba<u8>) {
+        self.fill_ring(center, 0.0, radius, color);
+    }
+
+    /// Fill the ring between two circles of the same center.
+    pub fn fill_ring(&mut self, center: Vec2, inner: f32, outer: f32, color: Rgba<u8>) {
+        let c = self.to_pixel(center);
+        let (inner, outer) = (inner * self.scale, outer * self.scale);
+        let (width, height) = self.image.dimensions();
+        let x_range = (c.x - outer).floor().max(0.0) as u32
+            ..(c.x + outer).ceil().clamp(0.0, width as f32) as u32;
+        let y_range = (c.y - outer).floor().max(0.0) as u32
+            ..(c.y + outer).ceil().clamp(0.0, height as f32) as u32;
+        for y in y_range {
+            for x in x_range.clone() {
+                let d = Vec2::new(x as f32 + 0.5, y as f32 + 0.5).distance(c);
+                if inner <= d && d <= outer {
+                    self.image.put_pixel(x, y, color);
+                }
+            }
+        }
+    }
+}
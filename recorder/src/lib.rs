@@ -0,0 +1,151 @@
+#![warn(missing_docs)]
+//! Headless recording of the games played by an agent, as animated GIFs or numbered PNG
+//! frames. The frames are rasterized in software, without window nor GPU, by the scenes of the
+//! games.
+pub mod canvas;
+
+pub use bevy_math::Vec2;
+pub use canvas::Canvas;
+pub use image::Rgba;
+
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, RgbaImage};
+use rl::ai::Agent;
+use rl::mdp::MarkovDecisionProcess;
+use std::error::Error;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Drawing of the state of a game.
+pub trait Scene<T: MarkovDecisionProcess> {
+    /// Draw the state of the game on the canvas.
+    fn draw(&self, e: &T, canvas: &mut Canvas);
+}
+
+/// Settings of a recording.
+#[derive(Debug, Clone)]
+pub struct RecordSettings {
+    /// Width of the frames in pixels.
+    pub width: u32,
+
+    /// Height of the frames in pixels.
+    pub height: u32,
+
+    /// Number of pixels per unit of the game.
+    pub scale: f32,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Maximal number of steps of the game.
+    pub max_steps: usize,
+
+    /// Number of steps between two frames.
+    pub frame_every: usize,
+
+    /// Seed of the game. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for RecordSettings {
+    fn default() -> Self {
+        RecordSettings {
+            width: 640,
+            height: 480,
+            scale: 1.0,
+            time_step: 0.1,
+            max_steps: 1_000,
+            frame_every: 1,
+            seed: None,
+        }
+    }
+}
+
+/// Frames of a game and the time between them.
+#[derive(Debug, Clone, Default)]
+pub struct Recording {
+    /// Frames, in order.
+    pub frames: Vec<RgbaImage>,
+
+    /// Time between two frames in milliseconds.
+    pub delay_ms: u32,
+}
+
+impl Recording {
+    /// Save the frames as an animated GIF if the path ends with `.gif`, or as numbered PNG
+    /// frames in the directory of the path otherwise.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let path = path.as_ref();
+        if path.extension().is_some_and(|ext| ext == "gif") {
+            self.save_gif(path)
+        } else {
+            self.save_frames(path)
+        }
+    }
+
+    /// Save the frames as an animated GIF playing in a loop.
+    pub fn save_gif<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        let mut encoder = GifEncoder::new_with_speed(BufWriter::new(File::create(path)?), 10);
+        encoder.set_repeat(Repeat::Infinite)?;
+        let delay = Delay::from_numer_denom_ms(self.delay_ms, 1);
+        encoder.encode_frames(
+            self.frames
+                .iter()
+                .map(|f| Frame::from_parts(f.clone(), 0, 0, delay)),
+        )?;
+        Ok(())
+    }
+
+    /// Save the frames in the directory, created if needed, as `frame_00000.png`,
+    /// `frame_00001.png`...
+    pub fn save_frames<P: AsRef<Path>>(&self, dir: P) -> Result<(), Box<dyn Error>> {
+        let dir = dir.as_ref();
+        fs::create_dir_all(dir)?;
+        for (i, f) in self.frames.iter().enumerate() {
+            f.save(dir.join(format!("frame_{i:05}.png")))?;
+        }
+        Ok(())
+    }
+}
+
+/// Play a game with the agent and draw a frame of the scene every `frame_every` steps, the
+/// first and the last states being always drawn.
+pub fn record<T, A, S>(
+    agent: &A,
+    e: &mut T,
+    scene: &S,
+    settings: &RecordSettings,
+) -> Result<Recording, Box<dyn Error>>
+where
+    T: MarkovDecisionProcess,
+    A: Agent<T> + ?Sized,
+    S: Scene<T>,
+{
+    let frame_every = settings.frame_every.max(1);
+    let draw = |e: &T| {
+        let mut canvas = Canvas::new(settings.width, settings.height, settings.scale);
+        scene.draw(e, &mut canvas);
+        canvas.into_image()
+    };
+
+    match settings.seed {
+        Some(s) => e.reset_with_seed(s),
+        None => e.reset(),
+    }
+    let mut recording = Recording {
+        frames: vec![draw(e)],
+        delay_ms: (1_000.0 * settings.time_step * frame_every as f32).round() as u32,
+    };
+    for step in 1..=settings.max_steps {
+        let transition = e.step(agent.policy(e)?, settings.time_step)?;
+        let done = transition.is_done() || step == settings.max_steps;
+        if step % frame_every == 0 || done {
+            recording.frames.push(draw(e));
+        }
+        if done {
+            break;
+        }
+    }
+    Ok(recording)
+}
@@ -0,0 +1,129 @@
+use candle_core::{Device, Tensor};
+use image::codecs::gif::GifDecoder;
+use image::AnimationDecoder;
+use recorder::{Canvas, RecordSettings, Rgba, Scene, Vec2};
+use rl::ai::Agent;
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
+use std::error::Error;
+use std::fs::File;
+use std::io::BufReader;
+
+const RED: Rgba<u8> = Rgba([255, 0, 0, 255]);
+
+#[derive(Debug, PartialEq)]
+struct Forward;
+
+impl DiscreteAction for Forward {
+    const COUNT: usize = 1;
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        (i == 0).then_some(Forward)
+    }
+}
+
+// Walk of five steps to the right
+struct Walk {
+    pos: u32,
+}
+
+impl MarkovDecisionProcess for Walk {
+    type Action = Forward;
+
+    fn reset(&mut self) {
+        self.pos = 0;
+    }
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, _: Forward, _: f32) -> Result<Transition, Box<dyn Error>> {
+        self.pos += 1;
+        Ok(Transition::new(self.feature(), -1.0, self.is_finished()))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pos == 5
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[self.pos as f32], &Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![5.0])
+    }
+}
+
+struct Walker;
+
+impl Agent<Walk> for Walker {
+    fn policy(&self, _: &Walk) -> Result<Forward, Box<dyn Error>> {
+        Ok(Forward)
+    }
+}
+
+// The walker is a dot moving to the right
+struct WalkScene;
+
+impl Scene<Walk> for WalkScene {
+    fn draw(&self, e: &Walk, canvas: &mut Canvas) {
+        canvas.fill_circle(Vec2::new(e.pos as f32 - 2.5, 0.0), 0.5, RED);
+    }
+}
+
+#[test]
+fn polygons_cover_the_pixels_whose_center_is_inside() {
+    let mut canvas = Canvas::new(10, 10, 1.0);
+    canvas.fill_polygon(
+        &[
+            Vec2::new(-2.0, -2.0),
+            Vec2::new(2.0, -2.0),
+            Vec2::new(2.0, 2.0),
+            Vec2::new(-2.0, 2.0),
+        ],
+        RED,
+    );
+    for (x, y, p) in canvas.image().enumerate_pixels() {
+        let inside = (3..7).contains(&x) && (3..7).contains(&y);
+        assert_eq!(*p == RED, inside, "pixel ({x}, {y})");
+    }
+
+    // The y axis points up
+    let mut canvas = Canvas::new(10, 10, 1.0);
+    canvas.fill_rect(Vec2::new(0.0, 4.0), Vec2::new(10.0, 2.0), 0.0, RED);
+    assert_eq!(*canvas.image().get_pixel(5, 0), RED);
+    assert_eq!(*canvas.image().get_pixel(5, 9), Rgba([0, 0, 0, 0]));
+}
+
+#[test]
+fn games_are_saved_as_gif_or_frames() {
+    let settings = RecordSettings {
+        width: 8,
+        height: 4,
+        frame_every: 2,
+        time_step: 0.05,
+        ..Default::default()
+    };
+    let recording = recorder::record(&Walker, &mut Walk { pos: 0 }, &WalkScene, &settings).unwrap();
+
+    // The first state, the steps 2 and 4, and the last state
+    assert_eq!(recording.frames.len(), 4);
+    assert_eq!(recording.delay_ms, 100);
+    assert_ne!(recording.frames[0], recording.frames[1]);
+
+    let path = std::env::temp_dir().join("recorder_games_are_saved.gif");
+    recording.save(&path).unwrap();
+    let decoder = GifDecoder::new(BufReader::new(File::open(&path).unwrap())).unwrap();
+    assert_eq!(decoder.into_frames().count(), 4);
+    std::fs::remove_file(path).unwrap();
+
+    let dir = std::env::temp_dir().join("recorder_games_are_saved");
+    recording.save(&dir).unwrap();
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 4);
+    assert!(dir.join("frame_00003.png").exists());
+    std::fs::remove_dir_all(dir).unwrap();
+}
@@ -1,6 +1,77 @@
+//! Agents playing Markov decision processes.
+use crate::error::LoadError;
+use crate::evaluation::{Episode, EvaluationReport, EvaluationSettings};
 use crate::mdp::MarkovDecisionProcess;
+use crate::space::DiscreteAction;
+use crate::vec_env::VecEnv;
 use candle_core::{Device, Tensor};
-use std::{collections::HashMap, convert::TryFrom, error::Error, path::PathBuf, u32};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::cell::RefCell;
+use std::sync::Mutex;
+use std::{collections::HashMap, convert::TryFrom, error::Error, path::PathBuf, thread};
+
+/// Way an agent picks its action from its distribution over the actions.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum InferenceMode {
+    /// Most probable action, as given by `Agent::policy`.
+    #[default]
+    Greedy,
+
+    /// Action sampled from the distribution.
+    Sampled,
+
+    /// Uniformly random action with the given probability, the greedy one otherwise.
+    EpsilonGreedy(f32),
+}
+
+/// Index sampled from a discrete distribution.
+pub fn sample_index<R: Rng>(probabilities: &[f32], rng: &mut R) -> usize {
+    let mut u = rng.gen::<f32>() * probabilities.iter().sum::<f32>();
+    for (i, p) in probabilities.iter().enumerate() {
+        if u < *p {
+            return i;
+        }
+        u -= p;
+    }
+    // Rounding errors can leave a tiny remainder
+    probabilities.len() - 1
+}
+
+/// Index of the highest value, the lowest index on ties.
+pub fn greedy_index(values: &[f32]) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .fold((0, f32::NEG_INFINITY), |(i_max, q_max), (i, &q)| {
+            if q > q_max {
+                (i, q)
+            } else {
+                (i_max, q_max)
+            }
+        })
+        .0
+}
+
+/// Uniformly random index with probability `epsilon`, the greedy one otherwise.
+pub fn epsilon_greedy_index<R: Rng>(values: &[f32], epsilon: f32, rng: &mut R) -> usize {
+    if rng.gen::<f32>() < epsilon {
+        rng.gen_range(0..values.len())
+    } else {
+        greedy_index(values)
+    }
+}
+
+// Distribution putting all the mass on the greedy action of the agent
+fn greedy_distribution<T, A>(agent: &A, s: &T) -> Result<Vec<f32>, Box<dyn Error>>
+where
+    T: MarkovDecisionProcess,
+    A: Agent<T> + ?Sized,
+{
+    let mut probs = vec![0.0; s.action_space().n()];
+    let i = agent.policy(s)?.index();
+    *probs.get_mut(i).ok_or("No action for this index")? = 1.0;
+    Ok(probs)
+}
 
 /// Agent trait for implementing AI that plays a game.
 pub trait Agent<T>
@@ -11,49 +82,364 @@ where
     /// Markov decision state.
     fn policy(&self, s: &T) -> Result<T::Action, Box<dyn Error>>;
 
+    /// Take one action for each environment of the vector. Agents able to process a batch of
+    /// features at once should override it.
+    fn batch_policy(&self, envs: &VecEnv<T>) -> Result<Vec<T::Action>, Box<dyn Error>> {
+        envs.envs().iter().map(|e| self.policy(e)).collect()
+    }
+
+    /// Probability of each action in the state, indexed like the actions. Agents without a
+    /// distribution, the default, put all the mass on the action of `policy`.
+    fn action_distribution(&self, s: &T) -> Result<Vec<f32>, Box<dyn Error>> {
+        greedy_distribution(self, s)
+    }
+
+    /// Take an action in the given inference mode, the random choices being drawn from `rng`.
+    fn act(
+        &self,
+        s: &T,
+        mode: InferenceMode,
+        rng: &mut StdRng,
+    ) -> Result<T::Action, Box<dyn Error>> {
+        let i = match mode {
+            InferenceMode::Greedy => return self.policy(s),
+            InferenceMode::Sampled => sample_index(&self.action_distribution(s)?, rng),
+            InferenceMode::EpsilonGreedy(epsilon) => {
+                if rng.gen::<f32>() >= epsilon {
+                    return self.policy(s);
+                }
+                rng.gen_range(0..s.action_space().n())
+            }
+        };
+        T::Action::from_index(i).ok_or_else(|| "No action for this index".into())
+    }
+
     /// Play the game until the end and return the total reward.
+    #[deprecated(note = "use `play_episode`, which also caps the number of steps")]
     fn play_game(&self, e: &mut T, time_step: Option<f32>) -> Result<f32, Box<dyn Error>> {
+        if e.is_finished() {
+            return Ok(0.0);
+        }
+        let episode = self.play_episode(e, time_step.unwrap_or(0.1), usize::MAX)?;
+        Ok(episode.total_reward)
+    }
+
+    /// Play the game until the end or until `max_steps` steps are played.
+    fn play_episode(
+        &self,
+        e: &mut T,
+        time_step: f32,
+        max_steps: usize,
+    ) -> Result<Episode, Box<dyn Error>> {
         let mut total_reward = 0.0;
-        let time_step = time_step.unwrap_or(0.1);
-        while !e.is_finished() {
+        for step in 0..max_steps {
             let a = self.policy(e)?;
-            total_reward += e.step(a, time_step)?;
+            let transition = e.step(a, time_step)?;
+            total_reward += transition.reward;
+            if transition.is_done() {
+                return Ok(Episode {
+                    total_reward,
+                    length: step + 1,
+                    success: transition.terminated,
+                });
+            }
         }
-        Ok(total_reward)
+        Ok(Episode {
+            total_reward,
+            length: max_steps,
+            success: false,
+        })
+    }
+
+    /// Reset the MDP with the seed and play the game, as the evaluations do for each of their
+    /// games. Agents drawing random actions override it to draw them from a generator derived
+    /// from the seed, which keeps the evaluations reproducible whatever the number of threads.
+    fn play_seeded_episode(
+        &self,
+        e: &mut T,
+        seed: u64,
+        time_step: f32,
+        max_steps: usize,
+    ) -> Result<Episode, Box<dyn Error>> {
+        e.reset_with_seed(seed);
+        self.play_episode(e, time_step, max_steps)
     }
 
     /// Monte-Carlo evaluation of the performance of the agent.
     fn evaluate(
         &self,
         e: &mut T,
-        nb_games: Option<u32>,
-        time_step: Option<f32>,
-    ) -> Result<f32, Box<dyn Error>> {
-        let n = nb_games.unwrap_or(1_000);
-        let mut res = 0.0;
-        for _ in 1..n {
-            e.reset();
-            res += self.play_game(e, time_step)?;
+        settings: &EvaluationSettings,
+    ) -> Result<EvaluationReport, Box<dyn Error>> {
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        let episodes = (0..settings.nb_games as u64)
+            .map(|i| {
+                self.play_seeded_episode(
+                    e,
+                    seed.wrapping_add(i),
+                    settings.time_step,
+                    settings.max_steps,
+                )
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(EvaluationReport { episodes })
+    }
+
+    /// Monte-Carlo evaluation playing the games on the environments of the vector, with one
+    /// batch of actions per step. With the same seed, the report is identical to the one of
+    /// `evaluate`.
+    fn evaluate_batched(
+        &self,
+        envs: &mut VecEnv<T>,
+        settings: &EvaluationSettings,
+    ) -> Result<EvaluationReport, Box<dyn Error>> {
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        let nb_games = settings.nb_games as usize;
+        if settings.max_steps == 0 {
+            // As in play_episode, the games are cut before their first step
+            return Ok(EvaluationReport {
+                episodes: vec![Episode::default(); nb_games],
+            });
+        }
+        let mut episodes = vec![None; nb_games];
+
+        // Game played by each environment and its outcome so far
+        let mut running = vec![None; envs.len()];
+        let mut next_game = 0;
+        let mut start = |envs: &mut VecEnv<T>, i: usize| {
+            if next_game == nb_games {
+                return None;
+            }
+            envs.env_mut(i)
+                .reset_with_seed(seed.wrapping_add(next_game as u64));
+            next_game += 1;
+            Some((next_game - 1, Episode::default()))
+        };
+        for (i, r) in running.iter_mut().enumerate() {
+            *r = start(envs, i);
+        }
+
+        while running.iter().any(Option::is_some) {
+            // The environments without a game left to play are not stepped
+            let actions = self
+                .batch_policy(envs)?
+                .into_iter()
+                .zip(&running)
+                .map(|(a, r)| r.as_ref().map(|_| a))
+                .collect();
+            let transitions = envs.step_some(actions, settings.time_step)?;
+            for (i, t) in transitions.into_iter().enumerate() {
+                let (Some(t), Some((game, episode))) = (t, &mut running[i]) else {
+                    continue;
+                };
+                episode.total_reward += t.reward;
+                episode.length += 1;
+                if t.is_done() || episode.length >= settings.max_steps {
+                    episode.success = t.terminated;
+                    episodes[*game] = Some(*episode);
+                    running[i] = start(envs, i);
+                }
+            }
+        }
+        Ok(EvaluationReport {
+            episodes: episodes.into_iter().flatten().collect(),
+        })
+    }
+
+    /// Monte-Carlo evaluation spreading the games over several threads, each playing on its own
+    /// copy of the MDP. With the same seed, the report is identical to the one of `evaluate`.
+    fn evaluate_parallel(
+        &self,
+        e: &T,
+        settings: &EvaluationSettings,
+        nb_threads: usize,
+    ) -> Result<EvaluationReport, Box<dyn Error>>
+    where
+        Self: Sized + Sync,
+        T: Clone + Send,
+    {
+        let seed = settings.seed.unwrap_or_else(rand::random);
+        let nb_games = settings.nb_games as usize;
+        let nb_threads = nb_threads.clamp(1, nb_games.max(1));
+
+        // The k-th thread plays the games k, k + nb_threads, k + 2 * nb_threads...
+        let results: Vec<Result<Vec<Episode>, String>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..nb_threads)
+                .map(|k| {
+                    let mut e = e.clone();
+                    s.spawn(move || {
+                        (k..nb_games)
+                            .step_by(nb_threads)
+                            .map(|i| {
+                                self.play_seeded_episode(
+                                    &mut e,
+                                    seed.wrapping_add(i as u64),
+                                    settings.time_step,
+                                    settings.max_steps,
+                                )
+                                .map_err(|err| err.to_string())
+                            })
+                            .collect()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().expect("An evaluation thread panicked"))
+                .collect()
+        });
+
+        let mut games = Vec::with_capacity(nb_threads);
+        for r in results {
+            games.push(r?.into_iter());
+        }
+        let episodes = (0..nb_games)
+            .filter_map(|i| games[i % nb_threads].next())
+            .collect();
+        Ok(EvaluationReport { episodes })
+    }
+}
+
+/// Agents estimating the value of each action, e.g. the table of a tabular agent or the outputs
+/// of a Q-network. The values of the perceptrons trained as policies are their logits.
+pub trait QFunction<T: MarkovDecisionProcess> {
+    /// Value of each action in the state, indexed like the actions.
+    fn q_values(&self, s: &T) -> Result<Vec<f32>, Box<dyn Error>>;
+}
+
+/// Agents estimating the value of a state. The agents with action values give the one of the
+/// best action.
+pub trait ValueFunction<T: MarkovDecisionProcess> {
+    /// Value of the state.
+    fn value(&self, s: &T) -> Result<f32, Box<dyn Error>>;
+}
+
+/// Agent playing in an inference mode with its own seeded random generator, which makes the
+/// stochastic modes usable wherever an agent is expected, e.g. by the evaluation. Each game of
+/// an evaluation draws from its own generator, derived from the seed of the agent and the one
+/// of the game, so the evaluations are reproducible even when spread over several threads.
+pub struct ModalAgent<A> {
+    /// Agent whose actions are played.
+    pub agent: A,
+
+    /// Way the actions are picked.
+    pub mode: InferenceMode,
+
+    seed: u64,
+    rng: Mutex<StdRng>,
+}
+
+impl<A> ModalAgent<A> {
+    /// Play the agent in the given mode. The generator is seeded at random if no seed is given.
+    pub fn new(agent: A, mode: InferenceMode, seed: Option<u64>) -> Self {
+        let seed = seed.unwrap_or_else(rand::random);
+        ModalAgent {
+            agent,
+            mode,
+            seed,
+            rng: Mutex::new(StdRng::seed_from_u64(seed)),
+        }
+    }
+}
+
+// Agent of a single game drawing its random choices from a generator of its own
+struct GameAgent<'a, A> {
+    agent: &'a A,
+    mode: InferenceMode,
+    rng: RefCell<StdRng>,
+}
+
+impl<T, A> Agent<T> for GameAgent<'_, A>
+where
+    T: MarkovDecisionProcess,
+    A: Agent<T>,
+{
+    fn policy(&self, s: &T) -> Result<T::Action, Box<dyn Error>> {
+        self.agent.act(s, self.mode, &mut self.rng.borrow_mut())
+    }
+}
+
+impl<T, A> Agent<T> for ModalAgent<A>
+where
+    T: MarkovDecisionProcess,
+    A: Agent<T>,
+{
+    fn policy(&self, s: &T) -> Result<T::Action, Box<dyn Error>> {
+        if self.mode == InferenceMode::Greedy {
+            return self.agent.policy(s);
+        }
+        let mut rng = self
+            .rng
+            .lock()
+            .map_err(|_| "The random generator of the agent is poisoned")?;
+        self.agent.act(s, self.mode, &mut rng)
+    }
+
+    fn play_seeded_episode(
+        &self,
+        e: &mut T,
+        seed: u64,
+        time_step: f32,
+        max_steps: usize,
+    ) -> Result<Episode, Box<dyn Error>> {
+        if self.mode == InferenceMode::Greedy {
+            return self
+                .agent
+                .play_seeded_episode(e, seed, time_step, max_steps);
+        }
+        let game_agent = GameAgent {
+            agent: &self.agent,
+            mode: self.mode,
+            rng: RefCell::new(StdRng::seed_from_u64(self.seed.wrapping_add(seed))),
+        };
+        e.reset_with_seed(seed);
+        game_agent.play_episode(e, time_step, max_steps)
+    }
+
+    fn batch_policy(&self, envs: &VecEnv<T>) -> Result<Vec<T::Action>, Box<dyn Error>> {
+        match self.mode {
+            InferenceMode::Greedy => self.agent.batch_policy(envs),
+            _ => envs.envs().iter().map(|e| self.policy(e)).collect(),
+        }
+    }
+
+    fn action_distribution(&self, s: &T) -> Result<Vec<f32>, Box<dyn Error>> {
+        match self.mode {
+            InferenceMode::Greedy => greedy_distribution(&self.agent, s),
+            InferenceMode::Sampled => self.agent.action_distribution(s),
+            InferenceMode::EpsilonGreedy(epsilon) => {
+                let probs = greedy_distribution(&self.agent, s)?;
+                let uniform = epsilon / probs.len() as f32;
+                Ok(probs
+                    .into_iter()
+                    .map(|p| (1.0 - epsilon) * p + uniform)
+                    .collect())
+            }
         }
-        Ok(res)
     }
 }
 
+/// Agents whose parameters can be read and replaced as one flat vector, which is what the
+/// black-box trainers perturb.
+pub trait FlatParameters: Sized {
+    /// Parameters of the agent, always in the same order.
+    fn parameters(&self) -> Result<Vec<f32>, Box<dyn Error>>;
+
+    /// Copy of the agent with other parameters, given in the order of `parameters`.
+    fn with_parameters(&self, parameters: &[f32]) -> Result<Self, Box<dyn Error>>;
+}
+
 /// Sub-trait that implements agents loading from safetensors file.
 pub trait FileLoader<T: MarkovDecisionProcess>:
-    Agent<T> + for<'a> TryFrom<&'a mut HashMap<String, Tensor>>
+    Agent<T> + for<'a> TryFrom<&'a mut HashMap<String, Tensor>, Error = LoadError>
 {
     /// Function that implements the loading of file.
-    fn from_file(file: PathBuf) -> Result<Self, ()> {
+    fn from_file(file: PathBuf) -> Result<Self, LoadError> {
         // Select the device. Try GPU and pick CPU if not found.
         let device = Device::cuda_if_available(0).unwrap_or(Device::Cpu);
 
-        let Ok(mut h) = candle_core::safetensors::load(file, &device) else {
-            return Err(());
-        };
-        let Ok(brain) = Self::try_from(&mut h) else {
-            return Err(());
-        };
-        Ok(brain)
+        let data = std::fs::read(file)?;
+        let mut h = candle_core::safetensors::load_buffer(&data, &device)?;
+        Self::try_from(&mut h)
     }
 }
@@ -0,0 +1,137 @@
+//! Uniform grids mapping continuous features to the rows of a table.
+use crate::error::{self, LoadError};
+use crate::space::BoxSpace;
+use candle_core::{DType, Device, Tensor};
+use std::collections::HashMap;
+use std::error::Error;
+
+/// Uniform grid over a box of the feature space. Features outside the box fall in the closest
+/// cell of the border.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Discretizer {
+    /// Lower bound of the grid along each feature.
+    pub low: Vec<f32>,
+
+    /// Upper bound of the grid along each feature.
+    pub high: Vec<f32>,
+
+    /// Number of bins along each feature.
+    pub bins: Vec<usize>,
+}
+
+impl Discretizer {
+    /// Grid over the box with the given number of bins along each feature. The bounds must be
+    /// finite and each feature needs at least one bin.
+    pub fn new(low: Vec<f32>, high: Vec<f32>, bins: Vec<usize>) -> Result<Self, Box<dyn Error>> {
+        let d = Discretizer { low, high, bins };
+        d.check()?;
+        Ok(d)
+    }
+
+    /// Grid over the observation space of a game.
+    pub fn from_space(space: &BoxSpace, bins: Vec<usize>) -> Result<Self, Box<dyn Error>> {
+        Discretizer::new(space.low.clone(), space.high.clone(), bins)
+    }
+
+    // Same number of bounds and bins, finite bounds in increasing order and no empty dimension
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.low.len() != self.bins.len() || self.high.len() != self.bins.len() {
+            return Err(format!(
+                "the grid has {} lower bounds, {} upper bounds and {} bins",
+                self.low.len(),
+                self.high.len(),
+                self.bins.len()
+            ));
+        }
+        for (i, ((low, high), n)) in self.low.iter().zip(&self.high).zip(&self.bins).enumerate() {
+            if !(low.is_finite() && high.is_finite() && low < high) {
+                return Err(format!("feature {i} has invalid bounds [{low}, {high}]"));
+            }
+            if *n == 0 {
+                return Err(format!("feature {i} has no bin"));
+            }
+        }
+        Ok(())
+    }
+
+    /// Number of cells of the grid.
+    pub fn nb_states(&self) -> usize {
+        self.bins.iter().product()
+    }
+
+    /// Bin of each feature.
+    pub fn cell(&self, x: &[f32]) -> Vec<usize> {
+        x.iter()
+            .zip(self.low.iter().zip(&self.high))
+            .zip(&self.bins)
+            .map(|((x, (low, high)), &n)| {
+                let bin = ((x - low) / (high - low) * n as f32).floor();
+                bin.clamp(0.0, (n - 1) as f32) as usize
+            })
+            .collect()
+    }
+
+    /// Index of the cell holding the features, the last feature varying the fastest.
+    pub fn index(&self, x: &[f32]) -> usize {
+        self.cell(x)
+            .iter()
+            .zip(&self.bins)
+            .fold(0, |index, (bin, n)| index * n + bin)
+    }
+
+    /// Index of the cell holding the feature tensor of a state.
+    pub fn index_tensor(&self, x: &Tensor) -> candle_core::Result<usize> {
+        Ok(self.index(&x.to_dtype(DType::F32)?.to_vec1::<f32>()?))
+    }
+
+    /// Write the grid as the tensors `{prefix}.low`, `{prefix}.high` and `{prefix}.bins`.
+    pub fn insert_tensors(
+        &self,
+        h: &mut HashMap<String, Tensor>,
+        prefix: &str,
+    ) -> candle_core::Result<()> {
+        let bins: Vec<u32> = self.bins.iter().map(|&n| n as u32).collect();
+        h.insert(
+            format!("{prefix}.low"),
+            Tensor::new(self.low.as_slice(), &Device::Cpu)?,
+        );
+        h.insert(
+            format!("{prefix}.high"),
+            Tensor::new(self.high.as_slice(), &Device::Cpu)?,
+        );
+        h.insert(
+            format!("{prefix}.bins"),
+            Tensor::new(bins.as_slice(), &Device::Cpu)?,
+        );
+        Ok(())
+    }
+
+    /// Read the grid written by `insert_tensors`.
+    pub fn take_tensors(h: &mut HashMap<String, Tensor>, prefix: &str) -> Result<Self, LoadError> {
+        let low = error::take_tensor(h, &format!("{prefix}.low"))?;
+        let high = error::take_tensor(h, &format!("{prefix}.high"))?;
+        let bins = error::take_tensor(h, &format!("{prefix}.bins"))?;
+        for (name, t) in [("high", &high), ("bins", &bins)] {
+            if t.dims() != low.dims() {
+                return Err(LoadError::ShapeMismatch {
+                    name: format!("{prefix}.{name}"),
+                    expected: low.dims().to_vec(),
+                    found: t.dims().to_vec(),
+                });
+            }
+        }
+        let d = Discretizer {
+            low: low.to_dtype(DType::F32)?.to_vec1()?,
+            high: high.to_dtype(DType::F32)?.to_vec1()?,
+            bins: bins
+                .to_dtype(DType::U32)?
+                .to_vec1::<u32>()?
+                .into_iter()
+                .map(|n| n as usize)
+                .collect(),
+        };
+        d.check()
+            .map_err(|e| LoadError::InvalidMetadata(format!("{prefix}: {e}")))?;
+        Ok(d)
+    }
+}
@@ -0,0 +1,130 @@
+//! Errors raised while loading agents from safetensors files.
+use candle_core::{Device, Tensor};
+use std::{collections::HashMap, error::Error, fmt, path::Path};
+
+/// Key of the tensor naming the kind of model stored in a file.
+pub const MODEL_KIND_KEY: &str = "metadata.model";
+
+/// Error raised while loading an agent.
+#[derive(Debug)]
+pub enum LoadError {
+    /// The file could not be read.
+    Io(std::io::Error),
+
+    /// The file is not a valid safetensors file, or its tensors could not be read or converted.
+    Tensor(candle_core::Error),
+
+    /// A tensor expected by the agent is not in the file.
+    MissingTensor(String),
+
+    /// A tensor of the file does not have the shape expected by the agent.
+    ShapeMismatch {
+        /// Name of the tensor.
+        name: String,
+        /// Shape expected by the agent.
+        expected: Vec<usize>,
+        /// Shape found in the file.
+        found: Vec<usize>,
+    },
+
+    /// The file stores another kind of model.
+    WrongModelKind {
+        /// Kind of model expected by the agent.
+        expected: &'static str,
+        /// Kind of model found in the file.
+        found: String,
+    },
+
+    /// A metadata of the file is not supported by the agent.
+    InvalidMetadata(String),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "cannot read the file: {e}"),
+            LoadError::Tensor(e) => write!(f, "cannot read the tensors: {e}"),
+            LoadError::MissingTensor(name) => write!(f, "tensor {name} is not in the file"),
+            LoadError::ShapeMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "tensor {name} has shape {found:?} instead of {expected:?}"
+            ),
+            LoadError::WrongModelKind { expected, found } => {
+                write!(f, "the file stores a {found} model instead of a {expected}")
+            }
+            LoadError::InvalidMetadata(reason) => write!(f, "invalid metadata: {reason}"),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            LoadError::Tensor(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for LoadError {
+    fn from(e: std::io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+impl From<candle_core::Error> for LoadError {
+    fn from(e: candle_core::Error) -> Self {
+        LoadError::Tensor(e)
+    }
+}
+
+/// Remove the tensor from the loaded file, or fail if it is missing.
+pub fn take_tensor(h: &mut HashMap<String, Tensor>, name: &str) -> Result<Tensor, LoadError> {
+    h.remove(name)
+        .ok_or_else(|| LoadError::MissingTensor(name.to_owned()))
+}
+
+/// Tensor storing a string as `u8` bytes, for the metadata of the files.
+pub fn string_tensor(s: &str) -> Tensor {
+    Tensor::new(s.as_bytes(), &Device::Cpu).expect("Bytes are a valid tensor")
+}
+
+/// Read a string stored as `u8` bytes.
+pub fn tensor_string(name: &str, t: &Tensor) -> Result<String, LoadError> {
+    t.to_vec1::<u8>()
+        .ok()
+        .and_then(|b| String::from_utf8(b).ok())
+        .ok_or_else(|| LoadError::InvalidMetadata(format!("{name} is not a string")))
+}
+
+/// Check that the file stores the expected kind of model. Files without a kind are accepted.
+pub fn check_model_kind(
+    h: &mut HashMap<String, Tensor>,
+    expected: &'static str,
+) -> Result<(), LoadError> {
+    match h.remove(MODEL_KIND_KEY) {
+        Some(t) => {
+            let found = tensor_string(MODEL_KIND_KEY, &t)?;
+            if found == expected {
+                Ok(())
+            } else {
+                Err(LoadError::WrongModelKind { expected, found })
+            }
+        }
+        None => Ok(()),
+    }
+}
+
+/// Kind of model stored in the file, if the file names it, e.g. to pick the agent loading it.
+pub fn model_kind<P: AsRef<Path>>(file: P) -> Result<Option<String>, LoadError> {
+    let data = std::fs::read(file)?;
+    let h = candle_core::safetensors::load_buffer(&data, &Device::Cpu)?;
+    h.get(MODEL_KIND_KEY)
+        .map(|t| tensor_string(MODEL_KIND_KEY, t))
+        .transpose()
+}
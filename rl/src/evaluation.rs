@@ -0,0 +1,98 @@
+//! Settings and statistics of the Monte-Carlo evaluation of agents.
+
+/// Settings of an evaluation.
+#[derive(Debug, Clone)]
+pub struct EvaluationSettings {
+    /// Number of games played.
+    pub nb_games: u32,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Maximal number of steps of a game. Longer games are cut and count as failures.
+    pub max_steps: usize,
+
+    /// Seed of the games. The `i`-th game is reset with the seed `seed + i`, so that the report
+    /// does not depend on the number of threads. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for EvaluationSettings {
+    fn default() -> Self {
+        EvaluationSettings {
+            nb_games: 1_000,
+            time_step: 0.1,
+            max_steps: 10_000,
+            seed: None,
+        }
+    }
+}
+
+/// Outcome of one evaluation game.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Episode {
+    /// Total reward collected.
+    pub total_reward: f32,
+
+    /// Number of steps played.
+    pub length: usize,
+
+    /// The game reached a terminal state before the step limit.
+    pub success: bool,
+}
+
+/// Games played during an evaluation and their statistics.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EvaluationReport {
+    /// Outcome of every game, in order.
+    pub episodes: Vec<Episode>,
+}
+
+impl EvaluationReport {
+    /// Total rewards of the games.
+    pub fn returns(&self) -> impl Iterator<Item = f32> + '_ {
+        self.episodes.iter().map(|e| e.total_reward)
+    }
+
+    /// Mean total reward.
+    pub fn mean(&self) -> f32 {
+        self.returns().sum::<f32>() / self.episodes.len().max(1) as f32
+    }
+
+    /// Standard deviation of the total rewards.
+    pub fn std(&self) -> f32 {
+        let mean = self.mean();
+        let variance = self.returns().map(|r| (r - mean).powi(2)).sum::<f32>()
+            / self.episodes.len().max(1) as f32;
+        variance.sqrt()
+    }
+
+    /// Bounds of the 95% confidence interval of the mean total reward, under the normal
+    /// approximation.
+    pub fn confidence_interval(&self) -> (f32, f32) {
+        let half_width = 1.96 * self.std() / (self.episodes.len().max(1) as f32).sqrt();
+        (self.mean() - half_width, self.mean() + half_width)
+    }
+
+    /// Lowest total reward.
+    pub fn min(&self) -> f32 {
+        self.returns().fold(f32::INFINITY, f32::min)
+    }
+
+    /// Highest total reward.
+    pub fn max(&self) -> f32 {
+        self.returns().fold(f32::NEG_INFINITY, f32::max)
+    }
+
+    /// Fraction of the games reaching a terminal state before the step limit.
+    pub fn success_rate(&self) -> f32 {
+        self.episodes.iter().filter(|e| e.success).count() as f32
+            / self.episodes.len().max(1) as f32
+    }
+
+    /// Mean number of steps of the games.
+    pub fn mean_length(&self) -> f32 {
+        self.episodes.iter().map(|e| e.length as f32).sum::<f32>()
+            / self.episodes.len().max(1) as f32
+    }
+}
@@ -2,4 +2,15 @@
 #![doc = include_str!("../README.md")]
 
 pub mod ai;
+pub mod discretizer;
+pub mod error;
+pub mod evaluation;
 pub mod mdp;
+pub mod mlp;
+pub mod normalization;
+pub mod space;
+pub mod tabular;
+pub mod trainer;
+pub mod trajectory;
+pub mod vec_env;
+pub mod wrappers;
@@ -1,21 +1,85 @@
+//! Markov decision processes and the outcome of their steps.
+use crate::space::{BoxSpace, Discrete, DiscreteAction};
 use candle_core::Tensor;
-use std::{error::Error, fmt::Debug};
+use std::{collections::HashMap, error::Error, fmt::Debug};
+
+/// Outcome of one step of a Markov decision process.
+#[derive(Debug, Clone)]
+pub struct Transition {
+    /// Feature tensor of the state reached after the step.
+    pub feature: Tensor,
+
+    /// Reward collected during the step.
+    pub reward: f32,
+
+    /// The MDP has reached a terminal state.
+    pub terminated: bool,
+
+    /// The episode has been cut before reaching a terminal state (e.g. by a time limit).
+    pub truncated: bool,
+
+    /// Extra diagnostics about the step, indexed by name.
+    pub info: HashMap<String, f32>,
+}
+
+impl Transition {
+    /// Create a transition that has not been truncated and carries no diagnostics.
+    pub fn new(feature: Tensor, reward: f32, terminated: bool) -> Self {
+        Transition {
+            feature,
+            reward,
+            terminated,
+            truncated: false,
+            info: HashMap::new(),
+        }
+    }
+
+    /// Add a diagnostic value to the transition.
+    pub fn with_info(mut self, key: &str, value: f32) -> Self {
+        self.info.insert(key.to_owned(), value);
+        self
+    }
+
+    /// Indicate if the episode is over, whether it terminated or was truncated.
+    pub fn is_done(&self) -> bool {
+        self.terminated || self.truncated
+    }
+}
 
 /// A trait for implementing Markov Decision processes.
 pub trait MarkovDecisionProcess {
     /// Action type.
-    type Action: Debug + PartialEq;
+    type Action: Debug + PartialEq + DiscreteAction;
 
     /// Reset the MDP to its initial state.
     fn reset(&mut self);
 
+    /// Seed the random number generator used by the MDP, so that the next resets are
+    /// reproducible.
+    fn seed(&mut self, seed: u64);
+
+    /// Seed the MDP and reset it to its initial state. Identical seeds give identical
+    /// trajectories.
+    fn reset_with_seed(&mut self, seed: u64) {
+        self.seed(seed);
+        self.reset();
+    }
+
     /// Take one step forward for the Markov decision process. As the function simulates a
     /// continuous dynamics, the time step is alsso given as an argument of the function.
-    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<f32, Box<dyn Error>>;
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>>;
 
     /// Indicate if the MDP has reached the terminal state.
     fn is_finished(&self) -> bool;
 
     /// Return the current set of the MDP as a feature tensor.
     fn feature(&self) -> Tensor;
+
+    /// Bounds and shape of the feature tensors.
+    fn observation_space(&self) -> BoxSpace;
+
+    /// Set of actions available at each step.
+    fn action_space(&self) -> Discrete {
+        Discrete(Self::Action::COUNT)
+    }
 }
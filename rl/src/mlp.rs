@@ -0,0 +1,361 @@
+//! Multi-layer perceptron agents usable with any game of the workspace.
+//!
+//! The perceptrons are saved in safetensors files with the following schema:
+//! - `layers.N.weight` and `layers.N.bias` for the `N`-th linear layer, starting from the input,
+//! - `metadata.input_size` and `metadata.output_size`, one-element `u32` tensors,
+//! - `metadata.activation`, the name of the activation function as `u8` bytes,
+//! - `metadata.model`, the kind of model, `mlp`, as `u8` bytes,
+//! - `obs_norm.count`, `obs_norm.mean` and `obs_norm.var`, the optional statistics normalizing
+//!   the features before the first layer.
+use crate::ai::{Agent, FileLoader, FlatParameters, QFunction, ValueFunction};
+use crate::error::{self, LoadError};
+use crate::mdp::MarkovDecisionProcess;
+use crate::normalization::RunningMeanStd;
+use crate::space::DiscreteAction;
+use crate::vec_env::VecEnv;
+use candle_core::{safetensors, DType, Device, Module, Tensor, D};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::path::Path;
+
+/// Name of the activation function between the layers, as written in the files.
+pub const ACTIVATION: &str = "relu";
+
+/// Kind of model written in the files.
+pub const MODEL_KIND: &str = "mlp";
+
+/// Perceptron with `I` inputs, `O` outputs and ReLU activations between the layers.
+pub struct MultiLayerPerceptron<const I: usize, const O: usize> {
+    /// Linear layers of the network, from the input to the output.
+    pub layers: Vec<candle_nn::Linear>,
+
+    /// Statistics normalizing the features of the game before they are given to the network.
+    pub obs_norm: Option<RunningMeanStd>,
+}
+
+impl<const I: usize, const O: usize> MultiLayerPerceptron<I, O> {
+    /// Create a perceptron whose variables are taken from the variable builder. The sizes of
+    /// the internal layers are given in order: there must be at least one, and none is empty.
+    pub fn new(
+        vs: candle_nn::VarBuilder,
+        intern_layers_sizes: &[usize],
+    ) -> candle_core::error::Result<Self> {
+        if intern_layers_sizes.is_empty() || intern_layers_sizes.contains(&0) {
+            return Err(candle_core::Error::Msg(format!(
+                "invalid internal layers {intern_layers_sizes:?}: the perceptron needs at least \
+                 one, each with a positive size"
+            )));
+        }
+        let mut nn = Self {
+            layers: Vec::with_capacity(2 + intern_layers_sizes.len()),
+            obs_norm: None,
+        };
+
+        // Push the first internal layer
+        nn.layers.push(candle_nn::linear(
+            I,
+            intern_layers_sizes[0],
+            vs.pp("layers.0"),
+        )?);
+
+        // Push the
+        for (i, w) in intern_layers_sizes.windows(2).enumerate() {
+            nn.layers.push(candle_nn::linear(
+                w[0],
+                w[1],
+                vs.pp(format!("layers.{}", i + 1)),
+            )?)
+        }
+        nn.layers.push(candle_nn::linear(
+            *intern_layers_sizes.last().unwrap(),
+            O,
+            vs.pp(format!("layers.{}", intern_layers_sizes.len())),
+        )?);
+
+        Ok(nn)
+    }
+
+    /// Create a perceptron with freshly initialized variables, for the trainers that do not
+    /// track its variables with a variable map.
+    pub fn random(intern_layers_sizes: &[usize]) -> candle_core::error::Result<Self> {
+        let varmap = candle_nn::VarMap::new();
+        Self::new(
+            candle_nn::VarBuilder::from_varmap(&varmap, DType::F32, &Device::Cpu),
+            intern_layers_sizes,
+        )
+    }
+
+    /// Save the perceptron and its metadata in a safetensors file.
+    pub fn save<P: AsRef<Path>>(&self, p: P) -> candle_core::error::Result<()> {
+        let mut h = HashMap::new();
+        for (i, l) in self.layers.iter().enumerate() {
+            h.insert(format!("layers.{i}.weight"), l.weight().clone());
+            if let Some(b) = l.bias() {
+                h.insert(format!("layers.{i}.bias"), b.clone());
+            }
+        }
+        h.insert(
+            "metadata.input_size".to_owned(),
+            Tensor::new(&[I as u32], &Device::Cpu)?,
+        );
+        h.insert(
+            "metadata.output_size".to_owned(),
+            Tensor::new(&[O as u32], &Device::Cpu)?,
+        );
+        h.insert(
+            "metadata.activation".to_owned(),
+            error::string_tensor(ACTIVATION),
+        );
+        h.insert(
+            error::MODEL_KIND_KEY.to_owned(),
+            error::string_tensor(MODEL_KIND),
+        );
+        if let Some(stats) = &self.obs_norm {
+            stats.insert_tensors(&mut h, "obs_norm")?;
+        }
+        safetensors::save(&h, p)
+    }
+
+    /// Normalize the features of the game with the statistics of the perceptron, if any.
+    pub fn normalize(&self, features: &Tensor) -> candle_core::Result<Tensor> {
+        match &self.obs_norm {
+            Some(stats) => stats.normalize_tensor(features),
+            None => Ok(features.clone()),
+        }
+    }
+
+    /// Probabilities of the actions given by the softmax of the outputs, the perceptron being
+    /// read as a stochastic policy. The features are normalized first and their last dimension
+    /// holds the features of a state, e.g. a batch of states.
+    pub fn probabilities(&self, features: &Tensor) -> candle_core::Result<Tensor> {
+        let logits = self.forward(&self.normalize(features)?)?;
+        candle_nn::ops::softmax(&logits, D::Minus1)
+    }
+}
+
+impl<const I: usize, const O: usize> Module for MultiLayerPerceptron<I, O> {
+    fn forward(&self, xs: &candle_core::Tensor) -> candle_core::error::Result<Tensor> {
+        let n = self.layers.len();
+        let logits = self.layers[..n - 1]
+            .iter()
+            .try_fold(xs.to_owned(), |acc, l| l.forward(&acc)?.relu())?;
+        let logits = self.layers[n - 1].forward(&logits)?;
+        Ok(logits)
+    }
+}
+
+impl<T, const I: usize, const O: usize> Agent<T> for MultiLayerPerceptron<I, O>
+where
+    T: MarkovDecisionProcess,
+{
+    fn policy(&self, e: &T) -> Result<T::Action, Box<dyn Error>> {
+        check_spaces::<T, I, O>(e)?;
+        let probs = self.probabilities(&e.feature().unsqueeze(0)?)?;
+        let i_max = probs.argmax(1)?.squeeze(0)?.to_scalar::<u32>()?;
+        T::Action::from_index(i_max as usize).ok_or_else(|| "No action for this index".into())
+    }
+
+    fn action_distribution(&self, s: &T) -> Result<Vec<f32>, Box<dyn Error>> {
+        check_spaces::<T, I, O>(s)?;
+        Ok(self
+            .probabilities(&s.feature().unsqueeze(0)?)?
+            .squeeze(0)?
+            .to_vec1::<f32>()?)
+    }
+
+    fn batch_policy(&self, envs: &VecEnv<T>) -> Result<Vec<T::Action>, Box<dyn Error>> {
+        if let Some(e) = envs.envs().first() {
+            check_spaces::<T, I, O>(e)?;
+        }
+        // One forward pass for the whole batch of features
+        self.forward(&self.normalize(&envs.features()?)?)?
+            .argmax(1)?
+            .to_vec1::<u32>()?
+            .into_iter()
+            .map(|i| {
+                T::Action::from_index(i as usize).ok_or_else(|| "No action for this index".into())
+            })
+            .collect()
+    }
+}
+
+impl<T, const I: usize, const O: usize> QFunction<T> for MultiLayerPerceptron<I, O>
+where
+    T: MarkovDecisionProcess,
+{
+    fn q_values(&self, s: &T) -> Result<Vec<f32>, Box<dyn Error>> {
+        check_spaces::<T, I, O>(s)?;
+        Ok(self
+            .forward(&self.normalize(&s.feature().unsqueeze(0)?)?)?
+            .squeeze(0)?
+            .to_vec1::<f32>()?)
+    }
+}
+
+// The value of a critic is its single output, and the one of a Q-network its largest output
+impl<T, const I: usize, const O: usize> ValueFunction<T> for MultiLayerPerceptron<I, O>
+where
+    T: MarkovDecisionProcess,
+{
+    fn value(&self, s: &T) -> Result<f32, Box<dyn Error>> {
+        let (d,) = s.observation_space().shape();
+        if d != I {
+            return Err(format!(
+                "Perceptron with {I} inputs cannot value a game with {d} features"
+            )
+            .into());
+        }
+        Ok(self
+            .forward(&self.normalize(&s.feature().unsqueeze(0)?)?)?
+            .max(D::Minus1)?
+            .squeeze(0)?
+            .to_scalar::<f32>()?)
+    }
+}
+
+// The weights of each layer are followed by its bias
+impl<const I: usize, const O: usize> FlatParameters for MultiLayerPerceptron<I, O> {
+    fn parameters(&self) -> Result<Vec<f32>, Box<dyn Error>> {
+        let mut p = Vec::new();
+        for l in &self.layers {
+            p.extend(l.weight().flatten_all()?.to_vec1::<f32>()?);
+            if let Some(b) = l.bias() {
+                p.extend(b.to_vec1::<f32>()?);
+            }
+        }
+        Ok(p)
+    }
+
+    fn with_parameters(&self, parameters: &[f32]) -> Result<Self, Box<dyn Error>> {
+        let mut rest = parameters;
+        let mut take = |t: &Tensor| -> Result<Tensor, Box<dyn Error>> {
+            let n = t.elem_count();
+            if rest.len() < n {
+                return Err("Too few parameters for the perceptron".into());
+            }
+            let (head, tail) = rest.split_at(n);
+            rest = tail;
+            Ok(Tensor::from_slice(head, t.shape(), t.device())?)
+        };
+        let mut layers = Vec::with_capacity(self.layers.len());
+        for l in &self.layers {
+            let w = take(l.weight())?;
+            let b = l.bias().map(&mut take).transpose()?;
+            layers.push(candle_nn::Linear::new(w, b));
+        }
+        if !rest.is_empty() {
+            return Err("Too many parameters for the perceptron".into());
+        }
+        Ok(MultiLayerPerceptron {
+            layers,
+            obs_norm: self.obs_norm.clone(),
+        })
+    }
+}
+
+// Check that the perceptron can play the game
+fn check_spaces<T: MarkovDecisionProcess, const I: usize, const O: usize>(
+    e: &T,
+) -> Result<(), Box<dyn Error>> {
+    let (d,) = e.observation_space().shape();
+    if d != I || e.action_space().n() != O {
+        return Err(format!(
+            "Perceptron with {I} inputs and {O} outputs cannot play a game with {d} features \
+             and {} actions",
+            e.action_space().n()
+        )
+        .into());
+    }
+    Ok(())
+}
+
+impl<const I: usize, const O: usize> TryFrom<&mut HashMap<String, Tensor>>
+    for MultiLayerPerceptron<I, O>
+{
+    type Error = LoadError;
+
+    fn try_from(h: &mut HashMap<String, Tensor>) -> Result<Self, Self::Error> {
+        error::check_model_kind(h, MODEL_KIND)?;
+
+        // Check the metadata when the file provides them
+        for (key, expected) in [("metadata.input_size", I), ("metadata.output_size", O)] {
+            if let Some(t) = h.remove(key) {
+                let found = t.to_dtype(DType::U32)?.to_vec1::<u32>()?;
+                if found != [expected as u32] {
+                    return Err(LoadError::ShapeMismatch {
+                        name: key.to_owned(),
+                        expected: vec![expected],
+                        found: found.into_iter().map(|x| x as usize).collect(),
+                    });
+                }
+            }
+        }
+        if let Some(t) = h.remove("metadata.activation") {
+            let name = error::tensor_string("metadata.activation", &t)?;
+            if name != ACTIVATION {
+                return Err(LoadError::InvalidMetadata(format!(
+                    "unsupported activation {name}"
+                )));
+            }
+        }
+
+        let obs_norm = RunningMeanStd::take_tensors(h, "obs_norm")?;
+        if let Some(stats) = &obs_norm {
+            if stats.mean.len() != I {
+                return Err(LoadError::ShapeMismatch {
+                    name: "obs_norm.mean".to_owned(),
+                    expected: vec![I],
+                    found: vec![stats.mean.len()],
+                });
+            }
+        }
+
+        let mut mlp = MultiLayerPerceptron::<I, O> {
+            layers: Vec::new(),
+            obs_norm,
+        };
+        let mut in_size = I;
+        loop {
+            let i = mlp.layers.len();
+            let name = format!("layers.{i}.weight");
+            let w = match h.remove(&name) {
+                Some(w) => w,
+                None if i > 0 => break,
+                None => return Err(LoadError::MissingTensor(name)),
+            };
+            let b = error::take_tensor(h, &format!("layers.{i}.bias"))?;
+            let out_size = w.dims().first().copied().unwrap_or(0);
+            if w.dims() != [out_size, in_size] {
+                return Err(LoadError::ShapeMismatch {
+                    name,
+                    expected: vec![out_size, in_size],
+                    found: w.dims().to_vec(),
+                });
+            }
+            if b.dims() != [out_size] {
+                return Err(LoadError::ShapeMismatch {
+                    name: format!("layers.{i}.bias"),
+                    expected: vec![out_size],
+                    found: b.dims().to_vec(),
+                });
+            }
+            mlp.layers.push(candle_nn::Linear::new(w, Some(b)));
+            in_size = out_size;
+        }
+        if in_size != O {
+            let last = mlp.layers.len() - 1;
+            return Err(LoadError::ShapeMismatch {
+                name: format!("layers.{last}.weight"),
+                expected: vec![O, mlp.layers[last].weight().dims()[1]],
+                found: mlp.layers[last].weight().dims().to_vec(),
+            });
+        }
+        Ok(mlp)
+    }
+}
+
+impl<T: MarkovDecisionProcess, const I: usize, const O: usize> FileLoader<T>
+    for MultiLayerPerceptron<I, O>
+{
+}
@@ -0,0 +1,138 @@
+//! Running statistics used to normalize the features and the rewards of the games.
+use crate::error::{self, LoadError};
+use crate::space::BoxSpace;
+use candle_core::{Device, Tensor};
+use std::collections::HashMap;
+
+/// Normalized values are clipped to this bound.
+pub const CLIP: f32 = 10.0;
+
+// Added to the variance before dividing by the standard deviation
+const EPSILON: f32 = 1e-8;
+
+/// Running mean and variance of vectors, updated one vector at a time.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunningMeanStd {
+    /// Number of vectors seen. It starts slightly above zero so that the first update replaces
+    /// the initial statistics.
+    pub count: f32,
+
+    /// Mean of each coordinate.
+    pub mean: Vec<f32>,
+
+    /// Variance of each coordinate.
+    pub var: Vec<f32>,
+}
+
+impl RunningMeanStd {
+    /// Statistics of vectors of dimension `dim`, starting from a zero mean and a unit variance.
+    pub fn new(dim: usize) -> Self {
+        RunningMeanStd {
+            count: 1e-4,
+            mean: vec![0.0; dim],
+            var: vec![1.0; dim],
+        }
+    }
+
+    /// Statistics mapping the bounded coordinates of the space to `[-1, 1]`, the unbounded
+    /// ones keeping a zero mean and a unit variance. They suit the agents whose features are not
+    /// normalized as they are collected.
+    pub fn from_space(space: &BoxSpace) -> Self {
+        let mut stats = RunningMeanStd::new(space.low.len());
+        for ((m, v), (l, h)) in stats
+            .mean
+            .iter_mut()
+            .zip(stats.var.iter_mut())
+            .zip(space.low.iter().zip(&space.high))
+        {
+            if l.is_finite() && h.is_finite() && l < h {
+                *m = (l + h) / 2.0;
+                *v = ((h - l) / 2.0).powi(2);
+            }
+        }
+        stats
+    }
+
+    /// Add a vector to the statistics.
+    pub fn update(&mut self, x: &[f32]) {
+        let total = self.count + 1.0;
+        for ((m, v), &x) in self.mean.iter_mut().zip(self.var.iter_mut()).zip(x) {
+            let delta = x - *m;
+            *m += delta / total;
+            *v = (*v * self.count + delta * delta * self.count / total) / total;
+        }
+        self.count = total;
+    }
+
+    /// Normalize a vector with the current statistics.
+    pub fn normalize(&self, x: &[f32]) -> Vec<f32> {
+        x.iter()
+            .zip(self.mean.iter().zip(&self.var))
+            .map(|(x, (m, v))| ((x - m) / (v + EPSILON).sqrt()).clamp(-CLIP, CLIP))
+            .collect()
+    }
+
+    /// Normalize a tensor whose last dimension holds the vectors, e.g. a batch of features.
+    pub fn normalize_tensor(&self, x: &Tensor) -> candle_core::Result<Tensor> {
+        let device = x.device();
+        let mean = Tensor::new(self.mean.as_slice(), device)?.to_dtype(x.dtype())?;
+        let std = (Tensor::new(self.var.as_slice(), device)?.to_dtype(x.dtype())?
+            + EPSILON as f64)?
+            .sqrt()?;
+        x.broadcast_sub(&mean)?
+            .broadcast_div(&std)?
+            .clamp(-CLIP, CLIP)
+    }
+
+    /// Write the statistics as the tensors `{prefix}.count`, `{prefix}.mean` and `{prefix}.var`.
+    pub fn insert_tensors(
+        &self,
+        h: &mut HashMap<String, Tensor>,
+        prefix: &str,
+    ) -> candle_core::Result<()> {
+        h.insert(
+            format!("{prefix}.count"),
+            Tensor::new(&[self.count], &Device::Cpu)?,
+        );
+        h.insert(
+            format!("{prefix}.mean"),
+            Tensor::new(self.mean.as_slice(), &Device::Cpu)?,
+        );
+        h.insert(
+            format!("{prefix}.var"),
+            Tensor::new(self.var.as_slice(), &Device::Cpu)?,
+        );
+        Ok(())
+    }
+
+    /// Read the statistics written by `insert_tensors`, if the file has them.
+    pub fn take_tensors(
+        h: &mut HashMap<String, Tensor>,
+        prefix: &str,
+    ) -> Result<Option<Self>, LoadError> {
+        let Some(mean) = h.remove(&format!("{prefix}.mean")) else {
+            return Ok(None);
+        };
+        let var = error::take_tensor(h, &format!("{prefix}.var"))?;
+        let count = error::take_tensor(h, &format!("{prefix}.count"))?;
+        if var.dims() != mean.dims() {
+            return Err(LoadError::ShapeMismatch {
+                name: format!("{prefix}.var"),
+                expected: mean.dims().to_vec(),
+                found: var.dims().to_vec(),
+            });
+        }
+        if count.dims() != [1] {
+            return Err(LoadError::ShapeMismatch {
+                name: format!("{prefix}.count"),
+                expected: vec![1],
+                found: count.dims().to_vec(),
+            });
+        }
+        Ok(Some(RunningMeanStd {
+            count: count.to_vec1::<f32>()?[0],
+            mean: mean.to_vec1()?,
+            var: var.to_vec1()?,
+        }))
+    }
+}
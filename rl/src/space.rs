@@ -0,0 +1,57 @@
+//! Descriptors of the observation and action spaces of Markov decision processes.
+use std::fmt::Debug;
+
+/// Box of the real space: each coordinate of the feature tensor lies between a lower and an
+/// upper bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BoxSpace {
+    /// Lower bound of each coordinate.
+    pub low: Vec<f32>,
+
+    /// Upper bound of each coordinate.
+    pub high: Vec<f32>,
+}
+
+impl BoxSpace {
+    /// Create a box from its bounds. Both bounds must have the same length.
+    pub fn new(low: Vec<f32>, high: Vec<f32>) -> Self {
+        assert_eq!(low.len(), high.len(), "Bounds of the box differ in length");
+        BoxSpace { low, high }
+    }
+
+    /// Shape of the feature tensors living in the box.
+    pub fn shape(&self) -> (usize,) {
+        (self.low.len(),)
+    }
+
+    /// Indicate if the point lies in the box.
+    pub fn contains(&self, x: &[f32]) -> bool {
+        x.len() == self.low.len()
+            && x.iter()
+                .zip(self.low.iter().zip(self.high.iter()))
+                .all(|(v, (l, h))| l <= v && v <= h)
+    }
+}
+
+/// Finite space of actions given by its cardinality.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Discrete(pub usize);
+
+impl Discrete {
+    /// Number of actions in the space.
+    pub fn n(&self) -> usize {
+        self.0
+    }
+}
+
+/// Mapping between the actions of a discrete action space and their index.
+pub trait DiscreteAction: Sized {
+    /// Number of actions.
+    const COUNT: usize;
+
+    /// Index of the action, lower than `COUNT`.
+    fn index(&self) -> usize;
+
+    /// Action of the given index, if any.
+    fn from_index(i: usize) -> Option<Self>;
+}
@@ -0,0 +1,123 @@
+//! Tabular agents usable with any game of the workspace.
+//!
+//! The tables are saved in safetensors files with the following schema:
+//! - `q_values`, the `(nb_states, nb_actions)` table of the action values,
+//! - `discretizer.low`, `discretizer.high` and `discretizer.bins`, the grid mapping the features
+//!   to the rows of the table,
+//! - `metadata.model`, the kind of model, `tabular`, as `u8` bytes.
+use crate::ai::{Agent, FileLoader, QFunction, ValueFunction};
+use crate::discretizer::Discretizer;
+use crate::error::{self, LoadError};
+use crate::mdp::MarkovDecisionProcess;
+use crate::space::DiscreteAction;
+use crate::trainer::tabular::QTable;
+use candle_core::{safetensors, Tensor};
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::path::Path;
+
+/// Kind of model written in the files.
+pub const MODEL_KIND: &str = "tabular";
+
+/// Agent playing the best action of the table in the cell of the grid holding the state.
+#[derive(Debug, Clone)]
+pub struct Tabular {
+    /// Values of the actions in each cell.
+    pub q: QTable,
+
+    /// Grid mapping the features to the rows of the table.
+    pub discretizer: Discretizer,
+}
+
+impl Tabular {
+    /// Gather the table and its grid, which must be valid and have one cell per row of the
+    /// table.
+    pub fn new(q: QTable, discretizer: Discretizer) -> Result<Self, LoadError> {
+        discretizer.check().map_err(LoadError::InvalidMetadata)?;
+        if q.nb_states() != discretizer.nb_states() {
+            return Err(LoadError::ShapeMismatch {
+                name: "q_values".to_owned(),
+                expected: vec![discretizer.nb_states(), q.nb_actions()],
+                found: vec![q.nb_states(), q.nb_actions()],
+            });
+        }
+        Ok(Tabular { q, discretizer })
+    }
+
+    /// Save the table and its grid in a safetensors file.
+    pub fn save<P: AsRef<Path>>(&self, p: P) -> candle_core::Result<()> {
+        let mut h = HashMap::new();
+        h.insert("q_values".to_owned(), self.q.to_tensor()?);
+        self.discretizer.insert_tensors(&mut h, "discretizer")?;
+        h.insert(
+            error::MODEL_KIND_KEY.to_owned(),
+            error::string_tensor(MODEL_KIND),
+        );
+        safetensors::save(&h, p)
+    }
+
+    // Row of the state, checking that the game has the dimensions of the grid and one action
+    // per column of the table
+    fn state<T: MarkovDecisionProcess>(&self, e: &T) -> Result<usize, Box<dyn Error>> {
+        let feature = e.feature().flatten_all()?;
+        if feature.elem_count() != self.discretizer.bins.len() {
+            return Err(format!(
+                "the game has {} features but the grid has {} dimensions",
+                feature.elem_count(),
+                self.discretizer.bins.len()
+            )
+            .into());
+        }
+        if self.q.nb_actions() != T::Action::COUNT {
+            return Err(format!(
+                "the game has {} actions but the table has {} columns",
+                T::Action::COUNT,
+                self.q.nb_actions()
+            )
+            .into());
+        }
+        Ok(self.discretizer.index_tensor(&feature)?)
+    }
+}
+
+impl<T: MarkovDecisionProcess> Agent<T> for Tabular {
+    fn policy(&self, e: &T) -> Result<T::Action, Box<dyn Error>> {
+        let s = self.state(e)?;
+        T::Action::from_index(self.q.greedy(s)).ok_or_else(|| "No action for this index".into())
+    }
+}
+
+impl<T: MarkovDecisionProcess> QFunction<T> for Tabular {
+    fn q_values(&self, s: &T) -> Result<Vec<f32>, Box<dyn Error>> {
+        let i = self.state(s)?;
+        Ok(self.q.row(i).to_vec())
+    }
+}
+
+impl<T: MarkovDecisionProcess> ValueFunction<T> for Tabular {
+    fn value(&self, s: &T) -> Result<f32, Box<dyn Error>> {
+        let i = self.state(s)?;
+        Ok(self.q.row(i)[self.q.greedy(i)])
+    }
+}
+
+impl TryFrom<&mut HashMap<String, Tensor>> for Tabular {
+    type Error = LoadError;
+
+    fn try_from(h: &mut HashMap<String, Tensor>) -> Result<Self, Self::Error> {
+        error::check_model_kind(h, MODEL_KIND)?;
+        let discretizer = Discretizer::take_tensors(h, "discretizer")?;
+        let q = error::take_tensor(h, "q_values")?;
+        if q.rank() != 2 {
+            return Err(LoadError::ShapeMismatch {
+                name: "q_values".to_owned(),
+                expected: vec![discretizer.nb_states(), *q.dims().last().unwrap_or(&0)],
+                found: q.dims().to_vec(),
+            });
+        }
+        Tabular::new(QTable::from_tensor(&q)?, discretizer)
+    }
+}
+
+impl<T: MarkovDecisionProcess> FileLoader<T> for Tabular {}
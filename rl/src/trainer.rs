@@ -0,0 +1,17 @@
+//! Algorithms training agents by playing Markov decision processes.
+use std::error::Error;
+
+pub mod black_box;
+pub mod cloning;
+pub mod dqn;
+pub mod ppo;
+pub mod reinforce;
+pub mod tabular;
+
+// Fail on the settings that must be positive, e.g. the periods of the schedules
+fn check_positive(settings: &[(&str, usize)]) -> Result<(), Box<dyn Error>> {
+    match settings.iter().find(|(_, value)| *value == 0) {
+        Some((name, _)) => Err(format!("{name} must be positive").into()),
+        None => Ok(()),
+    }
+}
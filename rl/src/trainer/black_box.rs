@@ -0,0 +1,333 @@
+//! Gradient-free trainers perturbing the flattened parameters of any agent: the cross-entropy
+//! method and the evolution strategies of OpenAI. Only the total rewards of the games are used,
+//! which suits small policies and rewards that gradients struggle with.
+use crate::ai::{Agent, FlatParameters};
+use crate::evaluation::EvaluationSettings;
+use crate::mdp::MarkovDecisionProcess;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::error::Error;
+use std::thread;
+
+/// Mean total reward of each agent of the population. The population is split over several
+/// threads, each playing on its own copy of the MDP, and all the agents play the same games.
+pub fn evaluate_population<T, A>(
+    population: &[A],
+    e: &T,
+    settings: &EvaluationSettings,
+    nb_threads: usize,
+) -> Result<Vec<f32>, Box<dyn Error>>
+where
+    T: MarkovDecisionProcess + Clone + Send,
+    A: Agent<T> + Sync,
+{
+    let settings = EvaluationSettings {
+        seed: Some(settings.seed.unwrap_or_else(rand::random)),
+        ..settings.clone()
+    };
+    let chunk_size = population.len().div_ceil(nb_threads.max(1)).max(1);
+
+    // Each thread evaluates a contiguous chunk of the population
+    let results: Vec<Result<Vec<f32>, String>> = thread::scope(|s| {
+        let handles: Vec<_> = population
+            .chunks(chunk_size)
+            .map(|agents| {
+                let mut e = e.clone();
+                let settings = &settings;
+                s.spawn(move || {
+                    agents
+                        .iter()
+                        .map(|a| {
+                            a.evaluate(&mut e, settings)
+                                .map(|report| report.mean())
+                                .map_err(|err| err.to_string())
+                        })
+                        .collect()
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .map(|h| h.join().expect("An evaluation thread panicked"))
+            .collect()
+    });
+
+    let mut scores = Vec::with_capacity(population.len());
+    for r in results {
+        scores.extend(r?);
+    }
+    Ok(scores)
+}
+
+// Sample of the standard normal distribution, drawn with the Box-Muller transform
+fn standard_normal<R: Rng>(rng: &mut R) -> f32 {
+    let u = 1.0 - rng.gen::<f32>();
+    let v = rng.gen::<f32>();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f32::consts::PI * v).cos()
+}
+
+fn seeded_rng(seed: Option<u64>) -> StdRng {
+    match seed {
+        Some(s) => StdRng::seed_from_u64(s),
+        None => StdRng::from_entropy(),
+    }
+}
+
+fn available_threads() -> usize {
+    thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Hyper-parameters of the cross-entropy method. The parameters are sampled from independent
+/// normal distributions, refitted at each generation on the best candidates.
+#[derive(Debug, Clone)]
+pub struct CemTrainer {
+    /// Number of candidates evaluated at each generation.
+    pub population: usize,
+
+    /// Fraction of the best candidates the distribution is refitted on.
+    pub elite_fraction: f32,
+
+    /// Standard deviation of the parameters around the ones of the initial agent.
+    pub initial_std: f32,
+
+    /// Added to the standard deviation of the elites, which keeps the search from collapsing
+    /// before it finds good candidates.
+    pub extra_std: f32,
+
+    /// Number of generations.
+    pub nb_generations: usize,
+
+    /// Games played by each candidate. Their seed is ignored: every generation draws its own
+    /// games from the seed of the trainer.
+    pub evaluation: EvaluationSettings,
+
+    /// Number of threads evaluating the population.
+    pub nb_threads: usize,
+
+    /// Seed of the sampling and of the games. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for CemTrainer {
+    fn default() -> Self {
+        CemTrainer {
+            population: 50,
+            elite_fraction: 0.2,
+            initial_std: 1.0,
+            extra_std: 0.05,
+            nb_generations: 50,
+            evaluation: EvaluationSettings {
+                nb_games: 4,
+                max_steps: 1_000,
+                ..Default::default()
+            },
+            nb_threads: available_threads(),
+            seed: None,
+        }
+    }
+}
+
+impl CemTrainer {
+    /// Train the agent on the MDP, starting from its parameters. The agent returned has the
+    /// mean parameters of the last distribution.
+    pub fn train<T, A>(&self, e: &T, agent: &A) -> Result<A, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess + Clone + Send,
+        A: Agent<T> + FlatParameters + Sync,
+    {
+        let mut rng = seeded_rng(self.seed);
+        let mut mean = agent.parameters()?;
+        let mut std = vec![self.initial_std; mean.len()];
+        let population = self.population.max(1);
+        let nb_elites =
+            ((population as f32 * self.elite_fraction).round() as usize).clamp(1, population);
+
+        for _ in 0..self.nb_generations {
+            let candidates: Vec<Vec<f32>> = (0..population)
+                .map(|_| {
+                    mean.iter()
+                        .zip(&std)
+                        .map(|(m, s)| m + s * standard_normal(&mut rng))
+                        .collect()
+                })
+                .collect();
+            let agents = candidates
+                .iter()
+                .map(|p| agent.with_parameters(p))
+                .collect::<Result<Vec<_>, _>>()?;
+            let settings = EvaluationSettings {
+                seed: Some(rng.gen()),
+                ..self.evaluation.clone()
+            };
+            let scores = evaluate_population(&agents, e, &settings, self.nb_threads)?;
+
+            let (elite_mean, elite_std) = fit_elites(&candidates, &scores, nb_elites);
+            mean = elite_mean;
+            std = elite_std.iter().map(|s| s + self.extra_std).collect();
+        }
+        agent.with_parameters(&mean)
+    }
+}
+
+/// Hyper-parameters of the evolution strategies of OpenAI. The gradient of the mean reward is
+/// estimated from antithetic perturbations of the parameters weighted by their centered ranks,
+/// and followed with Adam.
+#[derive(Debug, Clone)]
+pub struct EsTrainer {
+    /// Number of candidates evaluated at each generation, rounded up to an even number since
+    /// every perturbation is also evaluated with the opposite sign.
+    pub population: usize,
+
+    /// Standard deviation of the perturbations.
+    pub noise_std: f32,
+
+    /// Learning rate of Adam.
+    pub learning_rate: f32,
+
+    /// Weight decay pulling the parameters towards zero.
+    pub weight_decay: f32,
+
+    /// Number of generations.
+    pub nb_generations: usize,
+
+    /// Games played by each candidate. Their seed is ignored: every generation draws its own
+    /// games from the seed of the trainer.
+    pub evaluation: EvaluationSettings,
+
+    /// Number of threads evaluating the population.
+    pub nb_threads: usize,
+
+    /// Seed of the sampling and of the games. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for EsTrainer {
+    fn default() -> Self {
+        EsTrainer {
+            population: 50,
+            noise_std: 0.5,
+            learning_rate: 0.05,
+            weight_decay: 0.005,
+            nb_generations: 100,
+            evaluation: EvaluationSettings {
+                nb_games: 4,
+                max_steps: 1_000,
+                ..Default::default()
+            },
+            nb_threads: available_threads(),
+            seed: None,
+        }
+    }
+}
+
+impl EsTrainer {
+    /// Train the agent on the MDP, starting from its parameters.
+    pub fn train<T, A>(&self, e: &T, agent: &A) -> Result<A, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess + Clone + Send,
+        A: Agent<T> + FlatParameters + Sync,
+    {
+        const BETA1: f32 = 0.9;
+        const BETA2: f32 = 0.999;
+
+        let mut rng = seeded_rng(self.seed);
+        let mut theta = agent.parameters()?;
+        let d = theta.len();
+        let nb_pairs = self.population.div_ceil(2).max(1);
+        let (mut m, mut v) = (vec![0.0; d], vec![0.0; d]);
+
+        for t in 1..=self.nb_generations {
+            let noise: Vec<Vec<f32>> = (0..nb_pairs)
+                .map(|_| (0..d).map(|_| standard_normal(&mut rng)).collect())
+                .collect();
+            let agents = noise
+                .iter()
+                .flat_map(|eps| {
+                    [1.0, -1.0].map(|sign| {
+                        theta
+                            .iter()
+                            .zip(eps)
+                            .map(|(x, n)| x + sign * self.noise_std * n)
+                            .collect::<Vec<f32>>()
+                    })
+                })
+                .map(|p| agent.with_parameters(&p))
+                .collect::<Result<Vec<_>, _>>()?;
+            let settings = EvaluationSettings {
+                seed: Some(rng.gen()),
+                ..self.evaluation.clone()
+            };
+            let scores = evaluate_population(&agents, e, &settings, self.nb_threads)?;
+            let weights = centered_ranks(&scores);
+
+            // The candidates 2i and 2i + 1 share the perturbation i with opposite signs
+            let mut grad = vec![0.0; d];
+            for (i, eps) in noise.iter().enumerate() {
+                let w = weights[2 * i] - weights[2 * i + 1];
+                for (g, n) in grad.iter_mut().zip(eps) {
+                    *g += w * n;
+                }
+            }
+            let scale = 1.0 / (2 * nb_pairs) as f32 / self.noise_std;
+            let (c1, c2) = (1.0 - BETA1.powi(t as i32), 1.0 - BETA2.powi(t as i32));
+            for k in 0..d {
+                let g = grad[k] * scale - self.weight_decay * theta[k];
+                m[k] = BETA1 * m[k] + (1.0 - BETA1) * g;
+                v[k] = BETA2 * v[k] + (1.0 - BETA2) * g * g;
+                theta[k] += self.learning_rate * (m[k] / c1) / ((v[k] / c2).sqrt() + 1e-8);
+            }
+        }
+        agent.with_parameters(&theta)
+    }
+}
+
+/// Mean and standard deviation of each parameter over the `nb_elites` candidates with the best
+/// scores, the first candidates winning the ties.
+pub fn fit_elites(
+    candidates: &[Vec<f32>],
+    scores: &[f32],
+    nb_elites: usize,
+) -> (Vec<f32>, Vec<f32>) {
+    let mut order: Vec<usize> = (0..scores.len()).collect();
+    order.sort_by(|&i, &j| scores[j].total_cmp(&scores[i]));
+    let elites = &order[..nb_elites.min(order.len())];
+    let n = elites.len() as f32;
+    let d = candidates.first().map_or(0, Vec::len);
+    (0..d)
+        .map(|k| {
+            let mean = elites.iter().map(|&i| candidates[i][k]).sum::<f32>() / n;
+            let var = elites
+                .iter()
+                .map(|&i| (candidates[i][k] - mean).powi(2))
+                .sum::<f32>()
+                / n;
+            (mean, var.sqrt())
+        })
+        .unzip()
+}
+
+/// Ranks of the scores mapped evenly to `[-0.5, 0.5]`, the best score getting `0.5` and equal
+/// scores sharing their mean rank. They make the updates independent of the scale of the
+/// rewards and robust to outliers.
+pub fn centered_ranks(scores: &[f32]) -> Vec<f32> {
+    let n = scores.len();
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|&i, &j| scores[i].total_cmp(&scores[j]));
+    let mut ranks = vec![0.0; n];
+    let mut start = 0;
+    while start < n {
+        let mut end = start + 1;
+        while end < n && scores[order[end]] == scores[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end - 1) as f32 / 2.0;
+        for &i in &order[start..end] {
+            ranks[i] = if n > 1 {
+                rank / (n - 1) as f32 - 0.5
+            } else {
+                0.0
+            };
+        }
+        start = end;
+    }
+    ranks
+}
@@ -0,0 +1,253 @@
+//! Behaviour cloning: supervised training of a perceptron policy imitating recorded
+//! demonstrations, e.g. the games of a human player.
+use crate::mlp::MultiLayerPerceptron;
+use crate::normalization::RunningMeanStd;
+use crate::trajectory::{self, Trajectory};
+use candle_core::{DType, Device, Module, Tensor, D};
+use candle_nn::{AdamW, Optimizer, ParamsAdamW, VarBuilder, VarMap};
+use rand::seq::SliceRandom;
+use rand::{rngs::StdRng, SeedableRng};
+use std::error::Error;
+use std::fs;
+use std::path::Path;
+
+/// Actions taken by a demonstrator and the features of the states they were taken in.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Demonstrations {
+    /// Features of the states.
+    pub features: Vec<Vec<f32>>,
+
+    /// Indices of the actions taken.
+    pub actions: Vec<usize>,
+}
+
+impl Demonstrations {
+    /// Pair every step of the episodes with the state it was played from.
+    pub fn from_trajectories(trajectories: &[Trajectory]) -> Self {
+        let mut demonstrations = Demonstrations::default();
+        for t in trajectories {
+            for (i, s) in t.steps.iter().enumerate() {
+                demonstrations.features.push(t.feature(i).to_vec());
+                demonstrations.actions.push(s.action);
+            }
+        }
+        demonstrations
+    }
+
+    /// Load the episodes of JSON lines trajectory files. The `.jsonl` files of the directories
+    /// are loaded in the order of their names.
+    pub fn load<P: AsRef<Path>>(paths: &[P]) -> Result<Self, Box<dyn Error>> {
+        let mut trajectories = Vec::new();
+        for path in paths {
+            let path = path.as_ref();
+            if path.is_dir() {
+                let mut files: Vec<_> = fs::read_dir(path)?
+                    .map(|entry| entry.map(|e| e.path()))
+                    .collect::<Result<_, _>>()?;
+                files.retain(|f| f.extension().is_some_and(|ext| ext == "jsonl"));
+                files.sort();
+                for f in files {
+                    trajectories.extend(trajectory::load(f)?);
+                }
+            } else {
+                trajectories.extend(trajectory::load(path)?);
+            }
+        }
+        Ok(Demonstrations::from_trajectories(&trajectories))
+    }
+
+    /// Number of demonstrated actions.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Indicate if no action was demonstrated.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Number of times each of the `nb_actions` actions was demonstrated.
+    pub fn action_counts(&self, nb_actions: usize) -> Vec<usize> {
+        let mut counts = vec![0; nb_actions];
+        for &a in &self.actions {
+            if a < nb_actions {
+                counts[a] += 1;
+            }
+        }
+        counts
+    }
+
+    /// Shuffle the demonstrations and split them in two, the second part holding the given
+    /// fraction of them, e.g. to keep a validation set.
+    pub fn split(&self, fraction: f32, seed: Option<u64>) -> (Self, Self) {
+        let mut rng = match seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_entropy(),
+        };
+        let mut indices: Vec<usize> = (0..self.len()).collect();
+        indices.shuffle(&mut rng);
+        let nb_second = (fraction.clamp(0.0, 1.0) * self.len() as f32).round() as usize;
+        let (second, first) = indices.split_at(nb_second);
+        (self.select(first), self.select(second))
+    }
+
+    fn select(&self, indices: &[usize]) -> Self {
+        Demonstrations {
+            features: indices.iter().map(|&i| self.features[i].clone()).collect(),
+            actions: indices.iter().map(|&i| self.actions[i]).collect(),
+        }
+    }
+
+    // Features and actions as tensors, after checking their sizes
+    fn tensors<const I: usize, const O: usize>(
+        &self,
+        device: &Device,
+    ) -> Result<(Tensor, Tensor), Box<dyn Error>> {
+        if let Some(f) = self.features.iter().find(|f| f.len() != I) {
+            return Err(format!("the policy reads {I} features, not {}", f.len()).into());
+        }
+        if let Some(a) = self.actions.iter().find(|&&a| a >= O) {
+            return Err(format!("the policy has {O} actions, action {a} is out of range").into());
+        }
+        let x = Tensor::from_vec(self.features.concat(), (self.len(), I), device)?;
+        let actions: Vec<u32> = self.actions.iter().map(|&a| a as u32).collect();
+        let y = Tensor::from_vec(actions, self.len(), device)?;
+        Ok((x, y))
+    }
+}
+
+/// Fraction of the demonstrated actions that are the most probable action of the policy.
+pub fn accuracy<const I: usize, const O: usize>(
+    policy: &MultiLayerPerceptron<I, O>,
+    demonstrations: &Demonstrations,
+) -> Result<f32, Box<dyn Error>> {
+    if demonstrations.is_empty() {
+        return Ok(0.0);
+    }
+    let (x, y) = demonstrations.tensors::<I, O>(&Device::Cpu)?;
+    let predicted = policy.probabilities(&x)?.argmax(D::Minus1)?;
+    let correct = predicted
+        .eq(&y)?
+        .to_dtype(DType::F32)?
+        .sum_all()?
+        .to_scalar::<f32>()?;
+    Ok(correct / demonstrations.len() as f32)
+}
+
+/// Hyper-parameters of the behaviour cloning trainer. The perceptron learns the demonstrated
+/// actions as a classifier, with the cross-entropy of its softmax, and is saved in the usual
+/// perceptron files.
+#[derive(Debug, Clone)]
+pub struct BehaviourCloningTrainer {
+    /// Sizes of the internal layers of the policy.
+    pub hidden_layers: Vec<usize>,
+
+    /// Learning rate of the AdamW optimizer.
+    pub learning_rate: f64,
+
+    /// Number of passes over the demonstrations.
+    pub epochs: usize,
+
+    /// Number of demonstrations per gradient step.
+    pub batch_size: usize,
+
+    /// Weight each action by the inverse of its frequency, so that the rare actions are not
+    /// drowned by the frequent ones, e.g. the player waiting.
+    pub balance_actions: bool,
+
+    /// Normalize the features with their statistics over the demonstrations, saved with the
+    /// policy.
+    pub normalize_observations: bool,
+
+    /// Seed of the shuffling of the demonstrations. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for BehaviourCloningTrainer {
+    fn default() -> Self {
+        BehaviourCloningTrainer {
+            hidden_layers: vec![32, 32],
+            learning_rate: 1e-3,
+            epochs: 50,
+            batch_size: 64,
+            balance_actions: false,
+            normalize_observations: true,
+            seed: None,
+        }
+    }
+}
+
+impl BehaviourCloningTrainer {
+    /// Train a perceptron policy on the demonstrations. The network has `I` inputs, one per
+    /// feature, and `O` outputs, the logits of the actions.
+    pub fn train<const I: usize, const O: usize>(
+        &self,
+        demonstrations: &Demonstrations,
+    ) -> Result<MultiLayerPerceptron<I, O>, Box<dyn Error>> {
+        if demonstrations.is_empty() {
+            return Err("no demonstration to learn from".into());
+        }
+        let device = Device::Cpu;
+        let (x, y) = demonstrations.tensors::<I, O>(&device)?;
+
+        let varmap = VarMap::new();
+        let mut policy = MultiLayerPerceptron::<I, O>::new(
+            VarBuilder::from_varmap(&varmap, DType::F32, &device),
+            &self.hidden_layers,
+        )?;
+        if self.normalize_observations {
+            let mut stats = RunningMeanStd::new(I);
+            for f in &demonstrations.features {
+                stats.update(f);
+            }
+            policy.obs_norm = Some(stats);
+        }
+        let x = policy.normalize(&x)?;
+        let mut optimizer = AdamW::new(
+            varmap.all_vars(),
+            ParamsAdamW {
+                lr: self.learning_rate,
+                ..Default::default()
+            },
+        )?;
+
+        // Weight of each action, normalized so that the mean weight of a demonstration is one
+        let n = demonstrations.len();
+        let weights: Vec<f32> = if self.balance_actions {
+            let counts = demonstrations.action_counts(O);
+            let present = counts.iter().filter(|&&c| c > 0).count() as f32;
+            counts
+                .iter()
+                .map(|&c| match c {
+                    0 => 0.0,
+                    c => n as f32 / (present * c as f32),
+                })
+                .collect()
+        } else {
+            vec![1.0; O]
+        };
+        let weights = Tensor::new(weights.as_slice(), &device)?;
+
+        let mut rng = match self.seed {
+            Some(s) => StdRng::seed_from_u64(s),
+            None => StdRng::from_entropy(),
+        };
+        let mut indices: Vec<u32> = (0..n as u32).collect();
+        for _ in 0..self.epochs {
+            indices.shuffle(&mut rng);
+            for batch in indices.chunks(self.batch_size.max(1)) {
+                let batch = Tensor::new(batch, &device)?;
+                let xb = x.index_select(&batch, 0)?;
+                let yb = y.index_select(&batch, 0)?;
+                let log_probs = candle_nn::ops::log_softmax(&policy.forward(&xb)?, D::Minus1)?
+                    .gather(&yb.unsqueeze(1)?, 1)?
+                    .squeeze(1)?;
+                let w = weights.index_select(&yb, 0)?;
+                let total = w.sum_all()?.to_scalar::<f32>()? as f64;
+                let loss = (log_probs * &w)?.sum_all()?.affine(-1.0 / total, 0.0)?;
+                optimizer.backward_step(&loss)?;
+            }
+        }
+        Ok(policy)
+    }
+}
@@ -0,0 +1,356 @@
+//! Deep Q-Network trainer with experience replay and a target network.
+use crate::mdp::MarkovDecisionProcess;
+use crate::mlp::MultiLayerPerceptron;
+use crate::normalization::RunningMeanStd;
+use crate::space::DiscreteAction;
+use crate::vec_env::VecEnv;
+use candle_core::{DType, Device, Module, Tensor, D};
+use candle_nn::{AdamW, Optimizer, ParamsAdamW, VarBuilder, VarMap};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Transition stored in the replay buffer.
+#[derive(Debug, Clone)]
+pub struct Experience {
+    /// Features of the state the action was taken in.
+    pub feature: Vec<f32>,
+
+    /// Index of the action taken.
+    pub action: usize,
+
+    /// Reward collected.
+    pub reward: f32,
+
+    /// Features of the state reached.
+    pub next_feature: Vec<f32>,
+
+    /// The state reached is terminal.
+    pub terminated: bool,
+}
+
+/// Circular buffer of the last experiences, sampled uniformly to decorrelate the updates.
+pub struct ReplayBuffer {
+    capacity: usize,
+    position: usize,
+    experiences: Vec<Experience>,
+}
+
+impl ReplayBuffer {
+    /// Create an empty buffer holding at most `capacity` experiences, which must be positive.
+    pub fn new(capacity: usize) -> Self {
+        ReplayBuffer {
+            capacity,
+            position: 0,
+            experiences: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Number of experiences stored.
+    pub fn len(&self) -> usize {
+        self.experiences.len()
+    }
+
+    /// Indicate if the buffer holds no experience.
+    pub fn is_empty(&self) -> bool {
+        self.experiences.is_empty()
+    }
+
+    /// Store an experience, overwriting the oldest one when the buffer is full.
+    pub fn push(&mut self, experience: Experience) {
+        if self.experiences.len() < self.capacity {
+            self.experiences.push(experience);
+        } else {
+            self.experiences[self.position] = experience;
+        }
+        self.position = (self.position + 1) % self.capacity;
+    }
+
+    /// Sample a batch of experiences with replacement. Return the features, the actions, the
+    /// rewards, the next features and the termination flags as tensors.
+    pub fn sample<R: Rng>(
+        &self,
+        batch_size: usize,
+        rng: &mut R,
+        device: &Device,
+    ) -> candle_core::Result<(Tensor, Tensor, Tensor, Tensor, Tensor)> {
+        let batch: Vec<&Experience> = (0..batch_size)
+            .map(|_| &self.experiences[rng.gen_range(0..self.experiences.len())])
+            .collect();
+        let d = batch[0].feature.len();
+        let features: Vec<f32> = batch.iter().flat_map(|x| x.feature.clone()).collect();
+        let next_features: Vec<f32> = batch.iter().flat_map(|x| x.next_feature.clone()).collect();
+        let actions: Vec<u32> = batch.iter().map(|x| x.action as u32).collect();
+        let rewards: Vec<f32> = batch.iter().map(|x| x.reward).collect();
+        let terminated: Vec<f32> = batch.iter().map(|x| x.terminated as u8 as f32).collect();
+        Ok((
+            Tensor::from_vec(features, (batch_size, d), device)?,
+            Tensor::from_vec(actions, batch_size, device)?,
+            Tensor::from_vec(rewards, batch_size, device)?,
+            Tensor::from_vec(next_features, (batch_size, d), device)?,
+            Tensor::from_vec(terminated, batch_size, device)?,
+        ))
+    }
+}
+
+/// Mean Huber loss between the predictions and the targets: quadratic for errors smaller than
+/// one and linear beyond.
+pub fn huber_loss(predictions: &Tensor, targets: &Tensor) -> candle_core::Result<Tensor> {
+    let error = (predictions - targets)?.abs()?;
+    let quadratic = error.clamp(0.0, 1.0)?;
+    let linear = (&error - &quadratic)?;
+    ((quadratic.sqr()? * 0.5)? + linear)?.mean_all()
+}
+
+/// Hyper-parameters of the Deep Q-Network trainer.
+#[derive(Debug, Clone)]
+pub struct DqnTrainer {
+    /// Sizes of the internal layers of the perceptron.
+    pub hidden_layers: Vec<usize>,
+
+    /// Learning rate of the AdamW optimizer.
+    pub learning_rate: f64,
+
+    /// Discount factor of the future rewards.
+    pub discount: f32,
+
+    /// Number of experiences of a gradient step.
+    pub batch_size: usize,
+
+    /// Number of experiences kept in the replay buffer.
+    pub buffer_capacity: usize,
+
+    /// Number of steps played before the first gradient step.
+    pub learning_starts: usize,
+
+    /// Number of steps played between two gradient steps.
+    pub train_frequency: usize,
+
+    /// Number of steps played between two copies of the network into the target network.
+    pub target_update: usize,
+
+    /// Exploration rate at the beginning of the training.
+    pub epsilon_start: f32,
+
+    /// Exploration rate at the end of the schedule.
+    pub epsilon_end: f32,
+
+    /// Number of steps over which the exploration rate decreases linearly.
+    pub epsilon_decay_steps: usize,
+
+    /// Number of steps a random action is held once picked. Holding them lets the exploration
+    /// reach states that alternating random actions never reach.
+    pub exploration_repeat: usize,
+
+    /// Total number of steps played during the training, summed over the environments.
+    pub total_steps: usize,
+
+    /// Number of copies of the MDP played together, with one batched forward pass per step.
+    pub nb_envs: usize,
+
+    /// Maximal number of steps of a game before it is cut.
+    pub max_episode_steps: usize,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Normalize the features with their running statistics, saved with the network.
+    pub normalize_observations: bool,
+
+    /// File where the network is saved periodically.
+    pub checkpoint: Option<PathBuf>,
+
+    /// Number of steps played between two checkpoints.
+    pub checkpoint_every: usize,
+
+    /// Seed of the exploration and of the MDP. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for DqnTrainer {
+    fn default() -> Self {
+        DqnTrainer {
+            hidden_layers: vec![64, 64],
+            learning_rate: 1e-3,
+            discount: 0.99,
+            batch_size: 64,
+            buffer_capacity: 50_000,
+            learning_starts: 1_000,
+            train_frequency: 1,
+            target_update: 500,
+            epsilon_start: 1.0,
+            epsilon_end: 0.05,
+            epsilon_decay_steps: 20_000,
+            exploration_repeat: 40,
+            total_steps: 100_000,
+            nb_envs: 1,
+            max_episode_steps: 5_000,
+            time_step: 0.1,
+            normalize_observations: false,
+            checkpoint: None,
+            checkpoint_every: 10_000,
+            seed: None,
+        }
+    }
+}
+
+impl DqnTrainer {
+    /// Exploration rate after the given number of steps.
+    pub fn epsilon(&self, step: usize) -> f32 {
+        let progress = (step as f32 / self.epsilon_decay_steps.max(1) as f32).min(1.0);
+        self.epsilon_start + progress * (self.epsilon_end - self.epsilon_start)
+    }
+
+    /// Train a perceptron estimating the action values of the MDP. The network has `I` inputs,
+    /// one per feature, and `O` outputs, one per action. The experiences are collected on
+    /// `nb_envs` copies of the MDP.
+    pub fn train<T, const I: usize, const O: usize>(
+        &self,
+        e: &T,
+    ) -> Result<MultiLayerPerceptron<I, O>, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess + Clone,
+    {
+        super::check_positive(&[
+            ("buffer_capacity", self.buffer_capacity),
+            ("batch_size", self.batch_size),
+            ("train_frequency", self.train_frequency),
+            ("target_update", self.target_update),
+            ("checkpoint_every", self.checkpoint_every),
+        ])?;
+        let device = Device::Cpu;
+        let varmap = VarMap::new();
+        let mut model = MultiLayerPerceptron::<I, O>::new(
+            VarBuilder::from_varmap(&varmap, DType::F32, &device),
+            &self.hidden_layers,
+        )?;
+        if self.normalize_observations {
+            model.obs_norm = Some(RunningMeanStd::new(I));
+        }
+        let target_varmap = VarMap::new();
+        let target = MultiLayerPerceptron::<I, O>::new(
+            VarBuilder::from_varmap(&target_varmap, DType::F32, &device),
+            &self.hidden_layers,
+        )?;
+        sync(&varmap, &target_varmap)?;
+
+        let mut optimizer = AdamW::new(
+            varmap.all_vars(),
+            ParamsAdamW {
+                lr: self.learning_rate,
+                ..Default::default()
+            },
+        )?;
+        let mut buffer = ReplayBuffer::new(self.buffer_capacity);
+        let n = self.nb_envs.max(1);
+        let mut envs = VecEnv::new(vec![e.clone(); n]);
+        let mut rng = match self.seed {
+            Some(s) => {
+                envs.seed(s);
+                StdRng::seed_from_u64(s)
+            }
+            None => StdRng::from_entropy(),
+        };
+
+        envs.reset();
+        let mut episode_steps = vec![0; n];
+        let mut exploration = vec![None; n];
+        let mut step = 0;
+        while step < self.total_steps {
+            // Epsilon-greedy actions on the current network, random actions being held
+            // The raw features are stored, and normalized with the current statistics when used
+            let features = envs.features()?;
+            if let Some(stats) = &mut model.obs_norm {
+                for f in features.to_vec2::<f32>()? {
+                    stats.update(&f);
+                }
+            }
+            let greedy = model
+                .forward(&model.normalize(&features)?)?
+                .argmax(1)?
+                .to_vec1::<u32>()?;
+            let mut actions = Vec::with_capacity(n);
+            let mut indices = Vec::with_capacity(n);
+            for (i, explored) in exploration.iter_mut().enumerate() {
+                let a = match *explored {
+                    Some((a, remaining)) if remaining > 0 => {
+                        *explored = Some((a, remaining - 1));
+                        a
+                    }
+                    _ if rng.gen::<f32>() < self.epsilon(step + i) => {
+                        let a = rng.gen_range(0..O);
+                        *explored = Some((a, self.exploration_repeat.saturating_sub(1)));
+                        a
+                    }
+                    _ => {
+                        *explored = None;
+                        greedy[i] as usize
+                    }
+                };
+                actions.push(T::Action::from_index(a).ok_or("No action for this index")?);
+                indices.push(a);
+            }
+
+            let transitions = envs.step(actions, self.time_step)?;
+            for (i, transition) in transitions.into_iter().enumerate() {
+                episode_steps[i] += 1;
+                buffer.push(Experience {
+                    feature: features.get(i)?.to_vec1()?,
+                    action: indices[i],
+                    reward: transition.reward,
+                    next_feature: transition.feature.to_vec1()?,
+                    terminated: transition.terminated,
+                });
+                // Finished games are reset by the vector, the long ones are cut here
+                if !transition.is_done() && episode_steps[i] >= self.max_episode_steps {
+                    envs.env_mut(i).reset();
+                }
+                if transition.is_done() || episode_steps[i] >= self.max_episode_steps {
+                    episode_steps[i] = 0;
+                    exploration[i] = None;
+                }
+            }
+
+            // The schedules count the steps of every environment
+            for step in step..(step + n).min(self.total_steps) {
+                if step >= self.learning_starts && step % self.train_frequency == 0 {
+                    let (x, a, r, x_next, terminated) =
+                        buffer.sample(self.batch_size, &mut rng, &device)?;
+                    let q = model
+                        .forward(&model.normalize(&x)?)?
+                        .gather(&a.unsqueeze(1)?, 1)?
+                        .squeeze(1)?;
+                    // Bootstrap on the target network, except from terminal states
+                    let q_next = target
+                        .forward(&model.normalize(&x_next)?)?
+                        .max(D::Minus1)?
+                        .detach();
+                    let q_target = (r + (q_next * (1.0 - terminated)?)? * self.discount as f64)?;
+                    optimizer.backward_step(&huber_loss(&q, &q_target)?)?;
+                }
+                if step % self.target_update == 0 {
+                    sync(&varmap, &target_varmap)?;
+                }
+                if let Some(path) = &self.checkpoint {
+                    if (step + 1) % self.checkpoint_every == 0 {
+                        model.save(path)?;
+                    }
+                }
+            }
+            step += n;
+        }
+        if let Some(path) = &self.checkpoint {
+            model.save(path)?;
+        }
+        Ok(model)
+    }
+}
+
+// Copy the variables of the network into the target network
+fn sync(from: &VarMap, to: &VarMap) -> candle_core::Result<()> {
+    let from = from.data().lock().unwrap();
+    for (name, var) in to.data().lock().unwrap().iter() {
+        var.set(from[name].as_tensor())?;
+    }
+    Ok(())
+}
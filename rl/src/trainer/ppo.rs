@@ -0,0 +1,415 @@
+//! Proximal Policy Optimization with a clipped objective and generalized advantage estimation.
+//!
+//! The actor is a `MultiLayerPerceptron<I, O>` read as a softmax policy, and the critic a
+//! `MultiLayerPerceptron<I, 1>` estimating the value of the states. Both are saved in the usual
+//! perceptron files, the critic next to the actor with the `.critic.safetensors` extension, and
+//! a training can resume from them.
+use crate::ai::{sample_index, ValueFunction};
+use crate::mdp::MarkovDecisionProcess;
+use crate::mlp::MultiLayerPerceptron;
+use crate::normalization::RunningMeanStd;
+use crate::space::DiscreteAction;
+use crate::vec_env::VecEnv;
+use candle_core::{safetensors, DType, Device, Module, Tensor, D};
+use candle_nn::{AdamW, Optimizer, ParamsAdamW, VarBuilder, VarMap};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
+use std::error::Error;
+use std::path::{Path, PathBuf};
+
+/// Policy and value networks trained together.
+pub struct ActorCritic<const I: usize, const O: usize> {
+    /// Policy, whose outputs are the logits of the actions.
+    pub actor: MultiLayerPerceptron<I, O>,
+
+    /// Value of the states, reading the features normalized by the actor.
+    pub critic: MultiLayerPerceptron<I, 1>,
+}
+
+impl<const I: usize, const O: usize> ActorCritic<I, O> {
+    /// File of the critic saved next to the actor file.
+    pub fn critic_path<P: AsRef<Path>>(p: P) -> PathBuf {
+        p.as_ref().with_extension("critic.safetensors")
+    }
+
+    /// Save the actor in the file and the critic next to it.
+    pub fn save<P: AsRef<Path>>(&self, p: P) -> candle_core::Result<()> {
+        self.actor.save(&p)?;
+        self.critic.save(Self::critic_path(p))
+    }
+
+    /// Values of a batch of raw features.
+    pub fn values(&self, features: &Tensor) -> candle_core::Result<Tensor> {
+        self.critic
+            .forward(&self.actor.normalize(features)?)?
+            .squeeze(D::Minus1)
+    }
+}
+
+impl<T, const I: usize, const O: usize> ValueFunction<T> for ActorCritic<I, O>
+where
+    T: MarkovDecisionProcess,
+{
+    fn value(&self, s: &T) -> Result<f32, Box<dyn Error>> {
+        Ok(self.values(&s.feature().unsqueeze(0)?)?.to_vec1::<f32>()?[0])
+    }
+}
+
+/// Mean clipped surrogate objective of PPO: the advantages weighted by the probability ratios
+/// of the actions, the ratios being clipped to `1 ± clip_range` when it would increase the
+/// objective.
+pub fn clipped_objective(
+    ratio: &Tensor,
+    advantages: &Tensor,
+    clip_range: f32,
+) -> candle_core::Result<Tensor> {
+    let clipped = ratio.clamp(1.0 - clip_range, 1.0 + clip_range)?;
+    (ratio * advantages)?
+        .minimum(&(clipped * advantages)?)?
+        .mean_all()
+}
+
+/// Hyper-parameters of the PPO trainer.
+#[derive(Debug, Clone)]
+pub struct PpoTrainer {
+    /// Sizes of the internal layers of the actor and of the critic.
+    pub hidden_layers: Vec<usize>,
+
+    /// Learning rate of the AdamW optimizer.
+    pub learning_rate: f64,
+
+    /// Discount factor of the future rewards.
+    pub discount: f32,
+
+    /// Decay of the generalized advantage estimation, from the one-step temporal difference at
+    /// 0 to the Monte-Carlo return at 1.
+    pub gae_lambda: f32,
+
+    /// Bound of the change of the probability ratio of the actions during an update.
+    pub clip_range: f32,
+
+    /// Number of steps played by each environment between two updates.
+    pub rollout_steps: usize,
+
+    /// Number of passes over the rollout at each update.
+    pub nb_epochs: usize,
+
+    /// Number of steps of a gradient step.
+    pub minibatch_size: usize,
+
+    /// Weight of the loss of the critic.
+    pub value_coef: f32,
+
+    /// Weight of the entropy bonus of the policy, which delays its collapse on one action.
+    pub entropy_coef: f32,
+
+    /// Total number of steps played during the training, summed over the environments.
+    pub total_steps: usize,
+
+    /// Number of copies of the MDP played together, with one batched forward pass per step.
+    pub nb_envs: usize,
+
+    /// Maximal number of steps of a game before it is cut.
+    pub max_episode_steps: usize,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Normalize the features with their running statistics, saved with the actor.
+    pub normalize_observations: bool,
+
+    /// File where the actor is saved periodically, the critic being saved next to it.
+    pub checkpoint: Option<PathBuf>,
+
+    /// Number of updates between two checkpoints.
+    pub checkpoint_every: usize,
+
+    /// Start from the networks of the checkpoint files when they exist.
+    pub resume: bool,
+
+    /// Seed of the sampling and of the MDP. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for PpoTrainer {
+    fn default() -> Self {
+        PpoTrainer {
+            hidden_layers: vec![64, 64],
+            learning_rate: 3e-4,
+            discount: 0.99,
+            gae_lambda: 0.95,
+            clip_range: 0.2,
+            rollout_steps: 512,
+            nb_epochs: 4,
+            minibatch_size: 64,
+            value_coef: 0.5,
+            entropy_coef: 0.01,
+            total_steps: 100_000,
+            nb_envs: 4,
+            max_episode_steps: 1_000,
+            time_step: 0.1,
+            normalize_observations: false,
+            checkpoint: None,
+            checkpoint_every: 10,
+            resume: false,
+            seed: None,
+        }
+    }
+}
+
+// Steps of a rollout, indexed by step then environment
+struct Rollout {
+    features: Vec<f32>,
+    actions: Vec<u32>,
+    log_probs: Vec<f32>,
+    values: Vec<f32>,
+    rewards: Vec<f32>,
+    dones: Vec<bool>,
+}
+
+impl PpoTrainer {
+    /// Train an actor and a critic on the MDP. The networks have `I` inputs, one per feature,
+    /// and the actor has `O` outputs, the logits of the actions. The steps are collected on
+    /// `nb_envs` copies of the MDP.
+    pub fn train<T, const I: usize, const O: usize>(
+        &self,
+        e: &T,
+    ) -> Result<ActorCritic<I, O>, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess + Clone,
+    {
+        super::check_positive(&[
+            ("rollout_steps", self.rollout_steps),
+            ("nb_epochs", self.nb_epochs),
+            ("minibatch_size", self.minibatch_size),
+            ("nb_envs", self.nb_envs),
+            ("checkpoint_every", self.checkpoint_every),
+        ])?;
+        let device = Device::Cpu;
+        let mut varmap = VarMap::new();
+        let mut critic_varmap = VarMap::new();
+        let mut model = ActorCritic {
+            actor: MultiLayerPerceptron::<I, O>::new(
+                VarBuilder::from_varmap(&varmap, DType::F32, &device),
+                &self.hidden_layers,
+            )?,
+            critic: MultiLayerPerceptron::<I, 1>::new(
+                VarBuilder::from_varmap(&critic_varmap, DType::F32, &device),
+                &self.hidden_layers,
+            )?,
+        };
+        if self.normalize_observations {
+            model.actor.obs_norm = Some(RunningMeanStd::new(I));
+        }
+        if let Some(path) = self
+            .checkpoint
+            .as_ref()
+            .filter(|p| self.resume && p.exists())
+        {
+            varmap.load(path)?;
+            critic_varmap.load(ActorCritic::<I, O>::critic_path(path))?;
+            let mut h = safetensors::load(path, &device)?;
+            model.actor.obs_norm = RunningMeanStd::take_tensors(&mut h, "obs_norm")?;
+        }
+
+        let mut vars = varmap.all_vars();
+        vars.extend(critic_varmap.all_vars());
+        let mut optimizer = AdamW::new(
+            vars,
+            ParamsAdamW {
+                lr: self.learning_rate,
+                ..Default::default()
+            },
+        )?;
+        let n = self.nb_envs.max(1);
+        let mut envs = VecEnv::new(vec![e.clone(); n]);
+        let mut rng = match self.seed {
+            Some(s) => {
+                envs.seed(s);
+                StdRng::seed_from_u64(s)
+            }
+            None => StdRng::from_entropy(),
+        };
+
+        envs.reset();
+        let mut episode_steps = vec![0; n];
+        let nb_updates = self.total_steps.div_ceil(n * self.rollout_steps);
+        for update in 0..nb_updates {
+            let rollout = self.collect(&mut envs, &mut model, &mut episode_steps, &mut rng)?;
+            let last_values = model.values(&envs.features()?)?.to_vec1::<f32>()?;
+            let (advantages, returns) = self.advantages(
+                &rollout.rewards,
+                &rollout.values,
+                &rollout.dones,
+                &last_values,
+            );
+            self.optimize(
+                &mut optimizer,
+                &model,
+                rollout,
+                advantages,
+                returns,
+                &mut rng,
+            )?;
+
+            if let Some(path) = &self.checkpoint {
+                if (update + 1) % self.checkpoint_every == 0 {
+                    model.save(path)?;
+                }
+            }
+        }
+        if let Some(path) = &self.checkpoint {
+            model.save(path)?;
+        }
+        Ok(model)
+    }
+
+    // Play `rollout_steps` steps on every environment, sampling the actions from the actor
+    fn collect<T, const I: usize, const O: usize>(
+        &self,
+        envs: &mut VecEnv<T>,
+        model: &mut ActorCritic<I, O>,
+        episode_steps: &mut [usize],
+        rng: &mut StdRng,
+    ) -> Result<Rollout, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess,
+    {
+        let n = envs.len();
+        let size = n * self.rollout_steps;
+        let mut rollout = Rollout {
+            features: Vec::with_capacity(size * I),
+            actions: Vec::with_capacity(size),
+            log_probs: Vec::with_capacity(size),
+            values: Vec::with_capacity(size),
+            rewards: Vec::with_capacity(size),
+            dones: Vec::with_capacity(size),
+        };
+        for _ in 0..self.rollout_steps {
+            // The raw features are stored, and normalized with the current statistics when used
+            let features = envs.features()?;
+            if let Some(stats) = &mut model.actor.obs_norm {
+                for f in features.to_vec2::<f32>()? {
+                    stats.update(&f);
+                }
+            }
+            let probs = model.actor.probabilities(&features)?.to_vec2::<f32>()?;
+            let values = model.values(&features)?.to_vec1::<f32>()?;
+            let mut actions = Vec::with_capacity(n);
+            for p in &probs {
+                let a = sample_index(p, rng);
+                actions.push(T::Action::from_index(a).ok_or("No action for this index")?);
+                rollout.actions.push(a as u32);
+                rollout.log_probs.push(p[a].max(f32::MIN_POSITIVE).ln());
+            }
+            rollout
+                .features
+                .extend(features.flatten_all()?.to_vec1::<f32>()?);
+            rollout.values.extend(&values);
+
+            let transitions = envs.step(actions, self.time_step)?;
+            for (i, transition) in transitions.into_iter().enumerate() {
+                episode_steps[i] += 1;
+                let cut = !transition.is_done() && episode_steps[i] >= self.max_episode_steps;
+                let mut reward = transition.reward;
+                // Cut games are bootstrapped on the value of their last state
+                if cut || transition.truncated {
+                    let last = model.values(&transition.feature.unsqueeze(0)?)?;
+                    reward += self.discount * last.to_vec1::<f32>()?[0];
+                }
+                // Finished games are reset by the vector, the long ones are cut here
+                if cut {
+                    envs.env_mut(i).reset();
+                }
+                let done = transition.is_done() || cut;
+                if done {
+                    episode_steps[i] = 0;
+                }
+                rollout.rewards.push(reward);
+                rollout.dones.push(done);
+            }
+        }
+        Ok(rollout)
+    }
+
+    /// Generalized advantage estimates and returns of the steps of a rollout. The steps are
+    /// indexed by step then environment, and `last_values` are the values of the states the
+    /// environments are in after the rollout.
+    pub fn advantages(
+        &self,
+        rewards: &[f32],
+        values: &[f32],
+        dones: &[bool],
+        last_values: &[f32],
+    ) -> (Vec<f32>, Vec<f32>) {
+        let n = last_values.len();
+        let mut advantages = vec![0.0; rewards.len()];
+        let mut gae = vec![0.0; n];
+        let mut next_values = last_values.to_vec();
+        for k in (0..rewards.len()).rev() {
+            let i = k % n;
+            let not_done = if dones[k] { 0.0 } else { 1.0 };
+            let delta = rewards[k] + self.discount * next_values[i] * not_done - values[k];
+            gae[i] = delta + self.discount * self.gae_lambda * not_done * gae[i];
+            advantages[k] = gae[i];
+            next_values[i] = values[k];
+        }
+        let returns = advantages.iter().zip(values).map(|(a, v)| a + v).collect();
+        (advantages, returns)
+    }
+
+    // Minibatch epochs on the clipped surrogate objective, the value loss and the entropy bonus
+    fn optimize<const I: usize, const O: usize>(
+        &self,
+        optimizer: &mut AdamW,
+        model: &ActorCritic<I, O>,
+        rollout: Rollout,
+        advantages: Vec<f32>,
+        returns: Vec<f32>,
+        rng: &mut StdRng,
+    ) -> candle_core::Result<()> {
+        let device = Device::Cpu;
+        let size = rollout.actions.len();
+        let x = model
+            .actor
+            .normalize(&Tensor::from_vec(rollout.features, (size, I), &device)?)?;
+        let a = Tensor::from_vec(rollout.actions, size, &device)?;
+        let old_log_probs = Tensor::from_vec(rollout.log_probs, size, &device)?;
+        let advantages = Tensor::from_vec(advantages, size, &device)?;
+        let returns = Tensor::from_vec(returns, size, &device)?;
+
+        let mut indices: Vec<u32> = (0..size as u32).collect();
+        for _ in 0..self.nb_epochs {
+            indices.shuffle(rng);
+            for batch in indices.chunks(self.minibatch_size.max(1)) {
+                let batch = Tensor::new(batch, &device)?;
+                let x = x.index_select(&batch, 0)?;
+                let a = a.index_select(&batch, 0)?;
+                let old_log_probs = old_log_probs.index_select(&batch, 0)?;
+                let returns = returns.index_select(&batch, 0)?;
+                let mut advantages = advantages.index_select(&batch, 0)?;
+                if batch.dim(0)? > 1 {
+                    let centered = advantages.broadcast_sub(&advantages.mean_all()?)?;
+                    let std = (centered.sqr()?.mean_all()?.sqrt()? + 1e-8)?;
+                    advantages = centered.broadcast_div(&std)?;
+                }
+
+                let log_softmax =
+                    candle_nn::ops::log_softmax(&model.actor.forward(&x)?, D::Minus1)?;
+                let log_probs = log_softmax.gather(&a.unsqueeze(1)?, 1)?.squeeze(1)?;
+                let ratio = (log_probs - old_log_probs)?.exp()?;
+                let policy_loss = clipped_objective(&ratio, &advantages, self.clip_range)?.neg()?;
+                let entropy = (log_softmax.exp()? * &log_softmax)?
+                    .sum(D::Minus1)?
+                    .mean_all()?
+                    .neg()?;
+                let values = model.critic.forward(&x)?.squeeze(1)?;
+                let value_loss = (values - returns)?.sqr()?.mean_all()?;
+
+                let loss = ((policy_loss + (value_loss * self.value_coef as f64)?)?
+                    - (entropy * self.entropy_coef as f64)?)?;
+                optimizer.backward_step(&loss)?;
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,298 @@
+//! REINFORCE policy-gradient trainer with an optional learned baseline.
+use crate::ai::sample_index;
+use crate::mdp::MarkovDecisionProcess;
+use crate::mlp::MultiLayerPerceptron;
+use crate::normalization::RunningMeanStd;
+use crate::space::DiscreteAction;
+use candle_core::{DType, Device, Module, Tensor, D};
+use candle_nn::{AdamW, Optimizer, ParamsAdamW, VarBuilder, VarMap};
+use rand::{rngs::StdRng, SeedableRng};
+use std::error::Error;
+use std::path::PathBuf;
+
+/// Game played with the stochastic policy, as collected for a gradient step.
+#[derive(Debug, Clone, Default)]
+pub struct Trajectory {
+    /// Features of the states the actions were taken in.
+    pub features: Vec<Vec<f32>>,
+
+    /// Indices of the actions sampled.
+    pub actions: Vec<usize>,
+
+    /// Rewards collected after each action.
+    pub rewards: Vec<f32>,
+
+    /// Features of the state reached by the last action.
+    pub last_feature: Vec<f32>,
+
+    /// The game ended in a terminal state rather than being cut.
+    pub terminated: bool,
+}
+
+impl Trajectory {
+    /// Number of steps played.
+    pub fn len(&self) -> usize {
+        self.actions.len()
+    }
+
+    /// Indicate if no step was played.
+    pub fn is_empty(&self) -> bool {
+        self.actions.is_empty()
+    }
+
+    /// Sum of the rewards collected.
+    pub fn total_reward(&self) -> f32 {
+        self.rewards.iter().sum()
+    }
+
+    /// Discounted sum of the rewards collected from each step to the end of the game, followed
+    /// by `last_value`, the estimated value of the last state reached. The value of a terminal
+    /// state is zero.
+    pub fn returns(&self, discount: f32, last_value: f32) -> Vec<f32> {
+        let mut returns = vec![0.0; self.len()];
+        let mut g = if self.terminated { 0.0 } else { last_value };
+        for (ret, r) in returns.iter_mut().zip(&self.rewards).rev() {
+            g = r + discount * g;
+            *ret = g;
+        }
+        returns
+    }
+}
+
+/// Hyper-parameters of the REINFORCE trainer. The perceptron is read as a softmax policy whose
+/// actions are sampled during the training, and is saved in the usual perceptron files: the
+/// agents loaded from them play the most probable action.
+#[derive(Debug, Clone)]
+pub struct ReinforceTrainer {
+    /// Sizes of the internal layers of the policy and of the baseline.
+    pub hidden_layers: Vec<usize>,
+
+    /// Learning rate of the AdamW optimizer of the policy.
+    pub learning_rate: f64,
+
+    /// Learn a state value subtracted from the returns to reduce the variance of the gradient.
+    pub baseline: bool,
+
+    /// Learning rate of the AdamW optimizer of the baseline.
+    pub baseline_learning_rate: f64,
+
+    /// Discount factor of the future rewards.
+    pub discount: f32,
+
+    /// Number of games played for each gradient step.
+    pub batch_episodes: usize,
+
+    /// Number of gradient steps.
+    pub nb_updates: usize,
+
+    /// Standardize the advantages of each batch, which makes the step size independent of the
+    /// scale of the rewards.
+    pub normalize_advantages: bool,
+
+    /// Maximal number of steps of a game before it is cut.
+    pub max_episode_steps: usize,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Normalize the features with their running statistics, saved with the policy.
+    pub normalize_observations: bool,
+
+    /// File where the policy is saved periodically.
+    pub checkpoint: Option<PathBuf>,
+
+    /// Number of gradient steps between two checkpoints.
+    pub checkpoint_every: usize,
+
+    /// Seed of the sampling and of the MDP. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for ReinforceTrainer {
+    fn default() -> Self {
+        ReinforceTrainer {
+            hidden_layers: vec![32, 32],
+            learning_rate: 1e-3,
+            baseline: true,
+            baseline_learning_rate: 1e-2,
+            discount: 0.99,
+            batch_episodes: 8,
+            nb_updates: 200,
+            normalize_advantages: true,
+            max_episode_steps: 1_000,
+            time_step: 0.1,
+            normalize_observations: false,
+            checkpoint: None,
+            checkpoint_every: 20,
+            seed: None,
+        }
+    }
+}
+
+impl ReinforceTrainer {
+    /// Train a perceptron policy on the MDP. The network has `I` inputs, one per feature, and
+    /// `O` outputs, the logits of the actions.
+    pub fn train<T, const I: usize, const O: usize>(
+        &self,
+        e: &mut T,
+    ) -> Result<MultiLayerPerceptron<I, O>, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess,
+    {
+        super::check_positive(&[
+            ("batch_episodes", self.batch_episodes),
+            ("max_episode_steps", self.max_episode_steps),
+            ("checkpoint_every", self.checkpoint_every),
+        ])?;
+        let device = Device::Cpu;
+        let varmap = VarMap::new();
+        let mut policy = MultiLayerPerceptron::<I, O>::new(
+            VarBuilder::from_varmap(&varmap, DType::F32, &device),
+            &self.hidden_layers,
+        )?;
+        if self.normalize_observations {
+            policy.obs_norm = Some(RunningMeanStd::new(I));
+        }
+        let mut optimizer = AdamW::new(
+            varmap.all_vars(),
+            ParamsAdamW {
+                lr: self.learning_rate,
+                ..Default::default()
+            },
+        )?;
+
+        let baseline_varmap = VarMap::new();
+        let baseline = MultiLayerPerceptron::<I, 1>::new(
+            VarBuilder::from_varmap(&baseline_varmap, DType::F32, &device),
+            &self.hidden_layers,
+        )?;
+        let mut baseline_optimizer = AdamW::new(
+            baseline_varmap.all_vars(),
+            ParamsAdamW {
+                lr: self.baseline_learning_rate,
+                ..Default::default()
+            },
+        )?;
+
+        let mut rng = match self.seed {
+            Some(s) => {
+                e.seed(s);
+                StdRng::seed_from_u64(s)
+            }
+            None => StdRng::from_entropy(),
+        };
+
+        for update in 0..self.nb_updates {
+            let mut trajectories = Vec::with_capacity(self.batch_episodes);
+            for _ in 0..self.batch_episodes {
+                trajectories.push(self.play(e, &mut policy, &mut rng)?);
+            }
+
+            // Gather the steps of the batch, the raw features being normalized with the
+            // statistics of the end of the batch
+            let n: usize = trajectories.iter().map(Trajectory::len).sum();
+            let features: Vec<f32> = trajectories
+                .iter()
+                .flat_map(|t| t.features.iter().flatten().copied())
+                .collect();
+            let actions: Vec<u32> = trajectories
+                .iter()
+                .flat_map(|t| t.actions.iter().map(|&a| a as u32))
+                .collect();
+            let x = policy.normalize(&Tensor::from_vec(features, (n, I), &device)?)?;
+            let a = Tensor::from_vec(actions, n, &device)?;
+
+            // Cut games are bootstrapped on the baseline, otherwise the returns would depend on
+            // the time left before the cut, which the baseline cannot see
+            let last_values = if self.baseline {
+                let last: Vec<f32> = trajectories
+                    .iter()
+                    .flat_map(|t| t.last_feature.iter().copied())
+                    .collect();
+                let last = Tensor::from_vec(last, (trajectories.len(), I), &device)?;
+                baseline
+                    .forward(&policy.normalize(&last)?)?
+                    .squeeze(1)?
+                    .to_vec1::<f32>()?
+            } else {
+                vec![0.0; trajectories.len()]
+            };
+            let returns: Vec<f32> = trajectories
+                .iter()
+                .zip(last_values)
+                .flat_map(|(t, v)| t.returns(self.discount, v))
+                .collect();
+            let g = Tensor::from_vec(returns, n, &device)?;
+
+            let mut advantages = if self.baseline {
+                let values = baseline.forward(&x)?.squeeze(1)?;
+                baseline_optimizer.backward_step(&(&values - &g)?.sqr()?.mean_all()?)?;
+                (&g - values.detach())?
+            } else {
+                g
+            };
+            if self.normalize_advantages && n > 1 {
+                let mean = advantages.mean_all()?;
+                let centered = advantages.broadcast_sub(&mean)?;
+                let std = (centered.sqr()?.mean_all()?.sqrt()? + 1e-8)?;
+                advantages = centered.broadcast_div(&std)?;
+            }
+
+            // Gradient ascent on the log-probabilities of the actions weighted by the advantages
+            let log_probs = candle_nn::ops::log_softmax(&policy.forward(&x)?, D::Minus1)?
+                .gather(&a.unsqueeze(1)?, 1)?
+                .squeeze(1)?;
+            let loss = (log_probs * advantages)?.mean_all()?.neg()?;
+            optimizer.backward_step(&loss)?;
+
+            if let Some(path) = &self.checkpoint {
+                if (update + 1) % self.checkpoint_every == 0 {
+                    policy.save(path)?;
+                }
+            }
+        }
+        if let Some(path) = &self.checkpoint {
+            policy.save(path)?;
+        }
+        Ok(policy)
+    }
+
+    // Play a game sampling the actions from the policy, updating the statistics of the features
+    fn play<T, const I: usize, const O: usize>(
+        &self,
+        e: &mut T,
+        policy: &mut MultiLayerPerceptron<I, O>,
+        rng: &mut StdRng,
+    ) -> Result<Trajectory, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess,
+    {
+        let mut trajectory = Trajectory::default();
+        e.reset();
+        let mut feature = e.feature();
+        for _ in 0..self.max_episode_steps {
+            let raw = feature.to_vec1::<f32>()?;
+            if let Some(stats) = &mut policy.obs_norm {
+                stats.update(&raw);
+            }
+            let probs = policy
+                .probabilities(&feature.unsqueeze(0)?)?
+                .squeeze(0)?
+                .to_vec1::<f32>()?;
+            let a = sample_index(&probs, rng);
+            let action = T::Action::from_index(a).ok_or("No action for this index")?;
+            let transition = e.step(action, self.time_step)?;
+            trajectory.features.push(raw);
+            trajectory.actions.push(a);
+            trajectory.rewards.push(transition.reward);
+            trajectory.terminated = transition.terminated;
+            let done = transition.is_done();
+            feature = transition.feature;
+            if done {
+                break;
+            }
+        }
+        trajectory.last_feature = feature.to_vec1::<f32>()?;
+        Ok(trajectory)
+    }
+}
@@ -0,0 +1,179 @@
+//! Epsilon-greedy tabular Q-learning and SARSA over a discretised state space.
+use crate::ai::{epsilon_greedy_index, greedy_index};
+use crate::mdp::MarkovDecisionProcess;
+use crate::space::DiscreteAction;
+use candle_core::{DType, Tensor};
+use rand::{rngs::StdRng, SeedableRng};
+use std::error::Error;
+
+/// Temporal-difference method used to update the table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TdMethod {
+    /// Off-policy update bootstrapping on the best action of the next state.
+    #[default]
+    QLearning,
+
+    /// On-policy update bootstrapping on the action actually taken in the next state.
+    Sarsa,
+}
+
+/// Values of every action in every discrete state.
+#[derive(Debug, Clone)]
+pub struct QTable {
+    nb_actions: usize,
+    values: Vec<f32>,
+}
+
+impl QTable {
+    /// Create a table whose values are all equal to `init`.
+    pub fn new(nb_states: usize, nb_actions: usize, init: f32) -> Self {
+        QTable {
+            nb_actions,
+            values: vec![init; nb_states * nb_actions],
+        }
+    }
+
+    /// Read a table from a tensor of shape `(nb_states, nb_actions)`, with at least one action.
+    pub fn from_tensor(t: &Tensor) -> candle_core::Result<Self> {
+        let (_, nb_actions) = t.dims2()?;
+        if nb_actions == 0 {
+            return Err(candle_core::Error::Msg(
+                "the table has no action".to_owned(),
+            ));
+        }
+        Ok(QTable {
+            nb_actions,
+            values: t.to_dtype(DType::F32)?.flatten_all()?.to_vec1()?,
+        })
+    }
+
+    /// Number of discrete states.
+    pub fn nb_states(&self) -> usize {
+        self.values.len() / self.nb_actions
+    }
+
+    /// Number of actions.
+    pub fn nb_actions(&self) -> usize {
+        self.nb_actions
+    }
+
+    /// Values of the actions in the given state.
+    pub fn row(&self, s: usize) -> &[f32] {
+        &self.values[s * self.nb_actions..(s + 1) * self.nb_actions]
+    }
+
+    /// Index of the best action in the given state. Ties go to the lowest index.
+    pub fn greedy(&self, s: usize) -> usize {
+        greedy_index(self.row(s))
+    }
+
+    /// Convert the table into a tensor of shape `(nb_states, nb_actions)`.
+    pub fn to_tensor(&self) -> candle_core::Result<Tensor> {
+        Tensor::from_slice(
+            &self.values,
+            (self.nb_states(), self.nb_actions),
+            &candle_core::Device::Cpu,
+        )
+    }
+
+    fn get_mut(&mut self, s: usize, a: usize) -> &mut f32 {
+        &mut self.values[s * self.nb_actions + a]
+    }
+}
+
+/// Hyper-parameters of the tabular trainer.
+#[derive(Debug, Clone)]
+pub struct TabularTrainer {
+    /// Temporal-difference method.
+    pub method: TdMethod,
+
+    /// Step size of the updates.
+    pub learning_rate: f32,
+
+    /// Discount factor of the future rewards.
+    pub discount: f32,
+
+    /// Probability of taking a random action instead of the greedy one.
+    pub epsilon: f32,
+
+    /// Number of games played.
+    pub nb_episodes: u32,
+
+    /// Maximal number of steps of a game before it is cut.
+    pub max_steps: usize,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Seed of the exploration and of the MDP. Picked at random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for TabularTrainer {
+    fn default() -> Self {
+        TabularTrainer {
+            method: TdMethod::QLearning,
+            learning_rate: 0.1,
+            discount: 0.999,
+            epsilon: 0.01,
+            nb_episodes: 300,
+            max_steps: 100_000,
+            time_step: 0.1,
+            seed: None,
+        }
+    }
+}
+
+impl TabularTrainer {
+    /// Train a table of `nb_states` rows on the MDP. The `discretize` function maps the feature
+    /// tensor of a state to its row in the table.
+    pub fn train<T, F>(
+        &self,
+        e: &mut T,
+        nb_states: usize,
+        discretize: F,
+    ) -> Result<QTable, Box<dyn Error>>
+    where
+        T: MarkovDecisionProcess,
+        F: Fn(&Tensor) -> Result<usize, Box<dyn Error>>,
+    {
+        let mut q = QTable::new(nb_states, e.action_space().n(), 0.0);
+        let mut rng = match self.seed {
+            Some(s) => {
+                e.seed(s);
+                StdRng::seed_from_u64(s)
+            }
+            None => StdRng::from_entropy(),
+        };
+
+        for _ in 0..self.nb_episodes {
+            e.reset();
+            let mut s = discretize(&e.feature())?;
+            let mut a = epsilon_greedy_index(q.row(s), self.epsilon, &mut rng);
+            for _ in 0..self.max_steps {
+                let action = T::Action::from_index(a).ok_or("No action for this index")?;
+                let transition = e.step(action, self.time_step)?;
+                let s_next = discretize(&transition.feature)?;
+                let a_next = epsilon_greedy_index(q.row(s_next), self.epsilon, &mut rng);
+
+                // Terminal states have no future rewards to bootstrap on
+                let mut target = transition.reward;
+                if !transition.terminated {
+                    target += self.discount
+                        * match self.method {
+                            TdMethod::QLearning => q.row(s_next)[q.greedy(s_next)],
+                            TdMethod::Sarsa => q.row(s_next)[a_next],
+                        };
+                }
+                let value = q.get_mut(s, a);
+                *value += self.learning_rate * (target - *value);
+
+                if transition.is_done() {
+                    break;
+                }
+                (s, a) = (s_next, a_next);
+            }
+        }
+        Ok(q)
+    }
+}
@@ -0,0 +1,184 @@
+//! Step by step recording of the games, saved as JSON lines to be replayed or studied later.
+//!
+//! A file holds one JSON object per line. Each episode starts with a `reset` line giving the
+//! seed of the game, if known, and the features of its initial state. It is followed by one
+//! `step` line per step:
+//!
+//! ```text
+//! {"reset":{"seed":42,"feature":[-0.5,0.0]}}
+//! {"step":{"action":2,"reward":-1.0,"time_step":0.02,"feature":[-0.5,0.01],"terminated":false,"truncated":false}}
+//! ```
+use crate::mdp::Transition;
+use candle_core::DType;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+/// One recorded step of a game.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Step {
+    /// Index of the action played.
+    pub action: usize,
+
+    /// Reward collected during the step.
+    pub reward: f32,
+
+    /// Time step given to the MDP.
+    pub time_step: f32,
+
+    /// Features of the state reached after the step.
+    pub feature: Vec<f32>,
+
+    /// The MDP has reached a terminal state.
+    pub terminated: bool,
+
+    /// The episode has been cut before reaching a terminal state.
+    pub truncated: bool,
+}
+
+impl Step {
+    /// Record the outcome of the action of the given index.
+    pub fn new(action: usize, time_step: f32, t: &Transition) -> Result<Self, Box<dyn Error>> {
+        Ok(Step {
+            action,
+            reward: t.reward,
+            time_step,
+            feature: t.feature.flatten_all()?.to_dtype(DType::F32)?.to_vec1()?,
+            terminated: t.terminated,
+            truncated: t.truncated,
+        })
+    }
+
+    /// Indicate if the episode is over after this step.
+    pub fn is_done(&self) -> bool {
+        self.terminated || self.truncated
+    }
+}
+
+/// Recorded episode: its initial state and every step played from it.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Trajectory {
+    /// Seed the game was reset with, if known.
+    pub seed: Option<u64>,
+
+    /// Features of the initial state.
+    pub initial: Vec<f32>,
+
+    /// Steps, in order.
+    pub steps: Vec<Step>,
+}
+
+impl Trajectory {
+    /// Start an episode from the features of its initial state.
+    pub fn new(seed: Option<u64>, initial: Vec<f32>) -> Self {
+        Trajectory {
+            seed,
+            initial,
+            steps: Vec::new(),
+        }
+    }
+
+    /// Number of steps.
+    pub fn len(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Indicate if no step was played.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Features of the state reached after `i` steps, the initial state being the state 0.
+    pub fn feature(&self, i: usize) -> &[f32] {
+        match i {
+            0 => &self.initial,
+            i => &self.steps[i - 1].feature,
+        }
+    }
+
+    /// Time elapsed when each state is reached, from the initial state to the last one.
+    pub fn times(&self) -> Vec<f32> {
+        std::iter::once(0.0)
+            .chain(self.steps.iter().scan(0.0, |t, s| {
+                *t += s.time_step;
+                Some(*t)
+            }))
+            .collect()
+    }
+
+    /// Time elapsed during the episode.
+    pub fn duration(&self) -> f32 {
+        self.steps.iter().map(|s| s.time_step).sum()
+    }
+
+    /// Sum of the rewards of the steps.
+    pub fn total_reward(&self) -> f32 {
+        self.steps.iter().map(|s| s.reward).sum()
+    }
+
+    /// Indicate if the episode is over, whether it terminated or was truncated.
+    pub fn is_done(&self) -> bool {
+        self.steps.last().is_some_and(Step::is_done)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Line {
+    Reset {
+        seed: Option<u64>,
+        feature: Vec<f32>,
+    },
+    Step(Step),
+}
+
+/// Write the episodes as JSON lines.
+pub fn write<W: Write>(trajectories: &[Trajectory], mut writer: W) -> Result<(), Box<dyn Error>> {
+    for t in trajectories {
+        let reset = Line::Reset {
+            seed: t.seed,
+            feature: t.initial.clone(),
+        };
+        serde_json::to_writer(&mut writer, &reset)?;
+        writeln!(writer)?;
+        for s in &t.steps {
+            serde_json::to_writer(&mut writer, &Line::Step(s.clone()))?;
+            writeln!(writer)?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+/// Read the episodes written as JSON lines. Blank lines are skipped.
+pub fn read<R: BufRead>(reader: R) -> Result<Vec<Trajectory>, Box<dyn Error>> {
+    let mut trajectories: Vec<Trajectory> = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let parsed = serde_json::from_str(&line).map_err(|e| format!("line {}: {e}", i + 1))?;
+        match parsed {
+            Line::Reset { seed, feature } => trajectories.push(Trajectory::new(seed, feature)),
+            Line::Step(s) => trajectories
+                .last_mut()
+                .ok_or_else(|| format!("line {}: step recorded before any reset", i + 1))?
+                .steps
+                .push(s),
+        }
+    }
+    Ok(trajectories)
+}
+
+/// Save the episodes in a JSON lines file.
+pub fn save<P: AsRef<Path>>(trajectories: &[Trajectory], path: P) -> Result<(), Box<dyn Error>> {
+    write(trajectories, BufWriter::new(File::create(path)?))
+}
+
+/// Load the episodes of a JSON lines file.
+pub fn load<P: AsRef<Path>>(path: P) -> Result<Vec<Trajectory>, Box<dyn Error>> {
+    read(BufReader::new(File::open(path)?))
+}
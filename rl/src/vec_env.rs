@@ -0,0 +1,96 @@
+//! Several copies of a Markov decision process stepped together, for batched rollouts.
+use crate::mdp::{MarkovDecisionProcess, Transition};
+use candle_core::Tensor;
+use std::error::Error;
+
+/// Vector of environments stepped with one batch of actions. The environments whose episode is
+/// over are reset automatically.
+pub struct VecEnv<T: MarkovDecisionProcess> {
+    envs: Vec<T>,
+}
+
+impl<T: MarkovDecisionProcess> VecEnv<T> {
+    /// Gather the environments.
+    pub fn new(envs: Vec<T>) -> Self {
+        VecEnv { envs }
+    }
+
+    /// Number of environments.
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    /// Indicate if there is no environment.
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    /// Environments, in order.
+    pub fn envs(&self) -> &[T] {
+        &self.envs
+    }
+
+    /// Mutable access to the `i`-th environment.
+    pub fn env_mut(&mut self, i: usize) -> &mut T {
+        &mut self.envs[i]
+    }
+
+    /// Seed the `i`-th environment with `seed + i`.
+    pub fn seed(&mut self, seed: u64) {
+        for (i, e) in self.envs.iter_mut().enumerate() {
+            e.seed(seed.wrapping_add(i as u64));
+        }
+    }
+
+    /// Reset every environment.
+    pub fn reset(&mut self) {
+        self.envs.iter_mut().for_each(|e| e.reset());
+    }
+
+    /// Features of the environments, stacked into a tensor of shape `(N, D)`.
+    pub fn features(&self) -> candle_core::Result<Tensor> {
+        let features: Vec<Tensor> = self.envs.iter().map(|e| e.feature()).collect();
+        Tensor::stack(&features, 0)
+    }
+
+    /// Step every environment with its action. The transition of an environment whose episode
+    /// is over holds the features of its last state, and the environment is reset afterwards.
+    pub fn step(
+        &mut self,
+        actions: Vec<T::Action>,
+        time_step: f32,
+    ) -> Result<Vec<Transition>, Box<dyn Error>> {
+        let transitions = self.step_some(actions.into_iter().map(Some).collect(), time_step)?;
+        Ok(transitions.into_iter().flatten().collect())
+    }
+
+    /// Step the environments given an action, as `step` does, and leave the others untouched.
+    pub fn step_some(
+        &mut self,
+        actions: Vec<Option<T::Action>>,
+        time_step: f32,
+    ) -> Result<Vec<Option<Transition>>, Box<dyn Error>> {
+        if actions.len() != self.envs.len() {
+            return Err(format!(
+                "{} actions given for {} environments",
+                actions.len(),
+                self.envs.len()
+            )
+            .into());
+        }
+        self.envs
+            .iter_mut()
+            .zip(actions)
+            .map(|(e, a)| {
+                let Some(a) = a else {
+                    return Ok(None);
+                };
+                let transition = e.step(a, time_step)?;
+                if transition.is_done() {
+                    e.reset();
+                }
+                Ok(Some(transition))
+            })
+            .collect()
+    }
+}
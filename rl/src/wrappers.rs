@@ -0,0 +1,541 @@
+//! Markov decision processes wrapping another one to change its episodes. The wrappers
+//! dereference to the wrapped MDP and can be stacked.
+use crate::error::LoadError;
+use crate::evaluation::Episode;
+use crate::mdp::{MarkovDecisionProcess, Transition};
+use crate::mlp::MultiLayerPerceptron;
+use crate::normalization::{RunningMeanStd, CLIP};
+use crate::space::{BoxSpace, DiscreteAction};
+use crate::trajectory::{self, Step, Trajectory};
+use candle_core::Tensor;
+use std::error::Error;
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+
+/// Cut the episodes after a given number of steps. The last step of a cut episode is marked as
+/// truncated.
+#[derive(Clone)]
+pub struct TimeLimit<T: MarkovDecisionProcess> {
+    env: T,
+    max_steps: usize,
+    elapsed_steps: usize,
+}
+
+impl<T: MarkovDecisionProcess> TimeLimit<T> {
+    /// Wrap the MDP so that its episodes last at most `max_steps` steps.
+    pub fn new(env: T, max_steps: usize) -> Self {
+        TimeLimit {
+            env,
+            max_steps,
+            elapsed_steps: 0,
+        }
+    }
+
+    /// Number of steps played since the last reset.
+    pub fn elapsed_steps(&self) -> usize {
+        self.elapsed_steps
+    }
+
+    /// Number of steps left before the episode is cut.
+    pub fn remaining_steps(&self) -> usize {
+        self.max_steps.saturating_sub(self.elapsed_steps)
+    }
+
+    /// Indicate if the episode has been cut.
+    pub fn is_truncated(&self) -> bool {
+        self.elapsed_steps >= self.max_steps && !self.env.is_finished()
+    }
+
+    /// Unwrap the MDP.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+}
+
+impl<T: MarkovDecisionProcess> MarkovDecisionProcess for TimeLimit<T> {
+    type Action = T::Action;
+
+    fn reset(&mut self) {
+        self.elapsed_steps = 0;
+        self.env.reset();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        let mut transition = self.env.step(action, time_step)?;
+        self.elapsed_steps += 1;
+        if self.elapsed_steps >= self.max_steps && !transition.terminated {
+            transition.truncated = true;
+        }
+        Ok(transition)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.env.is_finished()
+    }
+
+    fn feature(&self) -> Tensor {
+        self.env.feature()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        self.env.observation_space()
+    }
+}
+
+/// Repeat each action over several steps of the wrapped MDP and sum their rewards. The
+/// repetition stops early when the episode is over.
+#[derive(Clone)]
+pub struct FrameSkip<T: MarkovDecisionProcess> {
+    env: T,
+    skip: usize,
+}
+
+impl<T: MarkovDecisionProcess> FrameSkip<T> {
+    /// Wrap the MDP so that each action is played `skip` times.
+    pub fn new(env: T, skip: usize) -> Self {
+        FrameSkip {
+            env,
+            skip: skip.max(1),
+        }
+    }
+
+    /// Unwrap the MDP.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+}
+
+impl<T: MarkovDecisionProcess> MarkovDecisionProcess for FrameSkip<T> {
+    type Action = T::Action;
+
+    fn reset(&mut self) {
+        self.env.reset();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        let a = action.index();
+        let mut total_reward = 0.0;
+        let mut transition = self.env.step(action, time_step)?;
+        total_reward += transition.reward;
+        for _ in 1..self.skip {
+            if transition.is_done() {
+                break;
+            }
+            let action = T::Action::from_index(a).ok_or("No action for this index")?;
+            transition = self.env.step(action, time_step)?;
+            total_reward += transition.reward;
+        }
+        transition.reward = total_reward;
+        Ok(transition)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.env.is_finished()
+    }
+
+    fn feature(&self) -> Tensor {
+        self.env.feature()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        self.env.observation_space()
+    }
+}
+
+/// Keep track of the total reward and of the length of the episodes. The last transition of an
+/// episode carries them in its diagnostics, under `episode.return` and `episode.length`.
+#[derive(Clone)]
+pub struct RecordEpisodeStatistics<T: MarkovDecisionProcess> {
+    env: T,
+    current: Episode,
+    last_episode: Option<Episode>,
+}
+
+impl<T: MarkovDecisionProcess> RecordEpisodeStatistics<T> {
+    /// Wrap the MDP to record its episodes.
+    pub fn new(env: T) -> Self {
+        RecordEpisodeStatistics {
+            env,
+            current: Episode::default(),
+            last_episode: None,
+        }
+    }
+
+    /// Outcome of the episode being played.
+    pub fn current_episode(&self) -> Episode {
+        self.current
+    }
+
+    /// Outcome of the last finished episode.
+    pub fn last_episode(&self) -> Option<Episode> {
+        self.last_episode
+    }
+
+    /// Unwrap the MDP.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+}
+
+impl<T: MarkovDecisionProcess> MarkovDecisionProcess for RecordEpisodeStatistics<T> {
+    type Action = T::Action;
+
+    fn reset(&mut self) {
+        self.current = Episode::default();
+        self.env.reset();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        let mut transition = self.env.step(action, time_step)?;
+        self.current.total_reward += transition.reward;
+        self.current.length += 1;
+        if transition.is_done() {
+            self.current.success = transition.terminated;
+            self.last_episode = Some(self.current);
+            transition = transition
+                .with_info("episode.return", self.current.total_reward)
+                .with_info("episode.length", self.current.length as f32);
+        }
+        Ok(transition)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.env.is_finished()
+    }
+
+    fn feature(&self) -> Tensor {
+        self.env.feature()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        self.env.observation_space()
+    }
+}
+
+/// Normalize the features with their running mean and standard deviation. The statistics are
+/// updated at each step while training, and frozen otherwise. An agent trained behind the
+/// wrapper gets the statistics with `export_stats`, so that it normalizes the raw features
+/// itself once saved and reloaded.
+#[derive(Clone)]
+pub struct NormalizeObservation<T: MarkovDecisionProcess> {
+    env: T,
+    stats: RunningMeanStd,
+
+    /// Update the statistics with the features met.
+    pub training: bool,
+}
+
+impl<T: MarkovDecisionProcess> NormalizeObservation<T> {
+    /// Wrap the MDP with fresh statistics, updated at each step.
+    pub fn new(env: T) -> Self {
+        let (d,) = env.observation_space().shape();
+        NormalizeObservation {
+            env,
+            stats: RunningMeanStd::new(d),
+            training: true,
+        }
+    }
+
+    /// Wrap the MDP with frozen statistics, e.g. the ones saved with an agent. They must have
+    /// the dimension of the features.
+    pub fn with_stats(env: T, stats: RunningMeanStd) -> Result<Self, LoadError> {
+        let (d,) = env.observation_space().shape();
+        if stats.mean.len() != d || stats.var.len() != d {
+            return Err(LoadError::ShapeMismatch {
+                name: "obs_norm.mean".to_owned(),
+                expected: vec![d],
+                found: vec![stats.mean.len()],
+            });
+        }
+        Ok(NormalizeObservation {
+            env,
+            stats,
+            training: false,
+        })
+    }
+
+    /// Current statistics of the features.
+    pub fn stats(&self) -> &RunningMeanStd {
+        &self.stats
+    }
+
+    /// Freeze the statistics and give them to the perceptron trained behind the wrapper. It
+    /// fails if the perceptron already normalizes its features, which would normalize them
+    /// twice.
+    pub fn export_stats<const I: usize, const O: usize>(
+        &mut self,
+        model: &mut MultiLayerPerceptron<I, O>,
+    ) -> Result<(), Box<dyn Error>> {
+        if model.obs_norm.is_some() {
+            return Err("the perceptron already normalizes its features".into());
+        }
+        if self.stats.mean.len() != I {
+            return Err(format!(
+                "the perceptron reads {I} features, not {}",
+                self.stats.mean.len()
+            )
+            .into());
+        }
+        self.training = false;
+        model.obs_norm = Some(self.stats.clone());
+        Ok(())
+    }
+
+    /// Unwrap the MDP.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+
+    fn normalize(&self, feature: &Tensor) -> Result<Tensor, Box<dyn Error>> {
+        if feature.dims() != [self.stats.mean.len()] {
+            return Err(format!(
+                "features of shape {:?} do not match statistics of dimension {}",
+                feature.dims(),
+                self.stats.mean.len()
+            )
+            .into());
+        }
+        Ok(self.stats.normalize_tensor(feature)?)
+    }
+}
+
+impl<T: MarkovDecisionProcess> MarkovDecisionProcess for NormalizeObservation<T> {
+    type Action = T::Action;
+
+    fn reset(&mut self) {
+        self.env.reset();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        let mut transition = self.env.step(action, time_step)?;
+        if self.training {
+            self.stats.update(&transition.feature.to_vec1::<f32>()?);
+        }
+        transition.feature = self.normalize(&transition.feature)?;
+        Ok(transition)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.env.is_finished()
+    }
+
+    // Features not matching the statistics make the steps fail, and panic here as the
+    // feature cannot be returned raw without feeding the agent a state it was not trained on
+    fn feature(&self) -> Tensor {
+        self.normalize(&self.env.feature())
+            .unwrap_or_else(|e| panic!("Cannot normalize the observation: {e}"))
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        let (d,) = self.env.observation_space().shape();
+        BoxSpace::new(vec![-CLIP; d], vec![CLIP; d])
+    }
+}
+
+/// Divide the rewards by the running standard deviation of the discounted return, so that their
+/// scale does not depend on the game.
+#[derive(Clone)]
+pub struct ScaleReward<T: MarkovDecisionProcess> {
+    env: T,
+    stats: RunningMeanStd,
+    discount: f32,
+    discounted_return: f32,
+}
+
+impl<T: MarkovDecisionProcess> ScaleReward<T> {
+    /// Wrap the MDP, the return being discounted with the discount factor of the training.
+    pub fn new(env: T, discount: f32) -> Self {
+        ScaleReward {
+            env,
+            stats: RunningMeanStd::new(1),
+            discount,
+            discounted_return: 0.0,
+        }
+    }
+
+    /// Current statistics of the discounted return.
+    pub fn stats(&self) -> &RunningMeanStd {
+        &self.stats
+    }
+
+    /// Unwrap the MDP.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+}
+
+impl<T: MarkovDecisionProcess> MarkovDecisionProcess for ScaleReward<T> {
+    type Action = T::Action;
+
+    fn reset(&mut self) {
+        self.discounted_return = 0.0;
+        self.env.reset();
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.env.seed(seed);
+    }
+
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        let mut transition = self.env.step(action, time_step)?;
+        self.discounted_return = self.discounted_return * self.discount + transition.reward;
+        self.stats.update(&[self.discounted_return]);
+        transition.reward /= (self.stats.var[0] + 1e-8).sqrt();
+        if transition.is_done() {
+            self.discounted_return = 0.0;
+        }
+        Ok(transition)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.env.is_finished()
+    }
+
+    fn feature(&self) -> Tensor {
+        self.env.feature()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        self.env.observation_space()
+    }
+}
+
+/// Record every step of the episodes, to save them as JSON lines and replay them. An episode
+/// starts at each reset, the first one starting from the state of the MDP when it is wrapped.
+#[derive(Clone)]
+pub struct TrajectoryRecorder<T: MarkovDecisionProcess> {
+    env: T,
+    seed: Option<u64>,
+    current: Trajectory,
+    finished: Vec<Trajectory>,
+}
+
+impl<T: MarkovDecisionProcess> TrajectoryRecorder<T> {
+    /// Wrap the MDP to record its episodes.
+    pub fn new(env: T) -> Self {
+        let current = Trajectory::new(None, features(&env.feature()));
+        TrajectoryRecorder {
+            env,
+            seed: None,
+            current,
+            finished: Vec::new(),
+        }
+    }
+
+    /// Episode being played.
+    pub fn current_trajectory(&self) -> &Trajectory {
+        &self.current
+    }
+
+    /// Episodes recorded so far, the one being played included if a step was played.
+    pub fn trajectories(&self) -> Vec<Trajectory> {
+        let mut trajectories = self.finished.clone();
+        if !self.current.is_empty() {
+            trajectories.push(self.current.clone());
+        }
+        trajectories
+    }
+
+    /// Forget the recorded episodes, and start the current one again from the current state.
+    pub fn clear(&mut self) {
+        self.finished.clear();
+        self.current = Trajectory::new(None, features(&self.env.feature()));
+    }
+
+    /// Save the recorded episodes in a JSON lines file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), Box<dyn Error>> {
+        trajectory::save(&self.trajectories(), path)
+    }
+
+    /// Unwrap the MDP.
+    pub fn into_inner(self) -> T {
+        self.env
+    }
+}
+
+impl<T: MarkovDecisionProcess> MarkovDecisionProcess for TrajectoryRecorder<T> {
+    type Action = T::Action;
+
+    fn reset(&mut self) {
+        self.env.reset();
+        let next = Trajectory::new(self.seed.take(), features(&self.env.feature()));
+        let last = std::mem::replace(&mut self.current, next);
+        if !last.is_empty() {
+            self.finished.push(last);
+        }
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.seed = Some(seed);
+        self.env.seed(seed);
+    }
+
+    fn step(&mut self, action: Self::Action, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        let a = action.index();
+        let transition = self.env.step(action, time_step)?;
+        self.current
+            .steps
+            .push(Step::new(a, time_step, &transition)?);
+        Ok(transition)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.env.is_finished()
+    }
+
+    fn feature(&self) -> Tensor {
+        self.env.feature()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        self.env.observation_space()
+    }
+}
+
+fn features(feature: &Tensor) -> Vec<f32> {
+    feature
+        .flatten_all()
+        .and_then(|f| f.to_vec1())
+        .expect("Features are a vector of floats")
+}
+
+macro_rules! deref_to_env {
+    ($wrapper:ident) => {
+        impl<T: MarkovDecisionProcess> Deref for $wrapper<T> {
+            type Target = T;
+
+            fn deref(&self) -> &T {
+                &self.env
+            }
+        }
+
+        impl<T: MarkovDecisionProcess> DerefMut for $wrapper<T> {
+            fn deref_mut(&mut self) -> &mut T {
+                &mut self.env
+            }
+        }
+    };
+}
+
+deref_to_env!(TimeLimit);
+deref_to_env!(FrameSkip);
+deref_to_env!(RecordEpisodeStatistics);
+deref_to_env!(NormalizeObservation);
+deref_to_env!(ScaleReward);
+deref_to_env!(TrajectoryRecorder);
@@ -0,0 +1,103 @@
+mod common;
+
+use candle_core::{Device, Module, Tensor};
+use common::Corridor;
+use rl::ai::{Agent, FlatParameters};
+use rl::evaluation::EvaluationSettings;
+use rl::mlp::MultiLayerPerceptron;
+use rl::trainer::black_box::{
+    centered_ranks, evaluate_population, fit_elites, CemTrainer, EsTrainer,
+};
+
+fn perceptron() -> MultiLayerPerceptron<1, 2> {
+    MultiLayerPerceptron::random(&[8]).unwrap()
+}
+
+fn evaluation() -> EvaluationSettings {
+    EvaluationSettings {
+        nb_games: 1,
+        max_steps: 20,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn parameters_round_trip() {
+    let mlp = perceptron();
+    let p = mlp.parameters().unwrap();
+    assert_eq!(p.len(), 8 + 8 + 8 * 2 + 2);
+
+    let copy = mlp.with_parameters(&p).unwrap();
+    let x = Tensor::new(&[[0.4f32], [1.0]], &Device::Cpu).unwrap();
+    assert_eq!(
+        mlp.forward(&x).unwrap().to_vec2::<f32>().unwrap(),
+        copy.forward(&x).unwrap().to_vec2::<f32>().unwrap()
+    );
+    assert!(mlp.with_parameters(&p[1..]).is_err());
+}
+
+#[test]
+fn centered_ranks_are_evenly_spread() {
+    assert_eq!(centered_ranks(&[3.0, -1.0, 7.0]), [0.0, -0.5, 0.5]);
+    assert_eq!(centered_ranks(&[2.0, 2.0, 5.0]), [-0.25, -0.25, 0.5]);
+}
+
+#[test]
+fn elites_are_the_best_candidates() {
+    let candidates = vec![
+        vec![0.0, 10.0],
+        vec![2.0, 20.0],
+        vec![4.0, 30.0],
+        vec![6.0, 40.0],
+    ];
+
+    // The candidates 1 and 3 share the best score
+    let (mean, std) = fit_elites(&candidates, &[1.0, 3.0, 2.0, 3.0], 2);
+    assert_eq!((mean, std), (vec![4.0, 30.0], vec![2.0, 10.0]));
+    let (mean, std) = fit_elites(&candidates, &[1.0, 3.0, 2.0, 3.0], 1);
+    assert_eq!((mean, std), (vec![2.0, 20.0], vec![0.0, 0.0]));
+}
+
+#[test]
+fn population_scores_do_not_depend_on_the_threads() {
+    let population: Vec<_> = (0..5).map(|_| perceptron()).collect();
+    let settings = EvaluationSettings {
+        seed: Some(11),
+        ..evaluation()
+    };
+    let mut e = Corridor { pos: 0 };
+    let expected: Vec<f32> = population
+        .iter()
+        .map(|a| a.evaluate(&mut e, &settings).unwrap().mean())
+        .collect();
+    for nb_threads in [1, 2, 3, 8] {
+        let scores = evaluate_population(&population, &e, &settings, nb_threads).unwrap();
+        assert_eq!(scores, expected);
+    }
+}
+
+#[test]
+fn trainers_keep_the_shape_of_the_agent() {
+    let mlp = perceptron();
+    let nb_parameters = mlp.parameters().unwrap().len();
+    let cem = CemTrainer {
+        population: 6,
+        nb_generations: 2,
+        evaluation: evaluation(),
+        nb_threads: 2,
+        seed: Some(3),
+        ..Default::default()
+    };
+    let trained = cem.train(&Corridor { pos: 0 }, &mlp).unwrap();
+    assert_eq!(trained.parameters().unwrap().len(), nb_parameters);
+    let es = EsTrainer {
+        population: 6,
+        nb_generations: 2,
+        evaluation: evaluation(),
+        nb_threads: 2,
+        seed: Some(3),
+        ..Default::default()
+    };
+    let trained = es.train(&Corridor { pos: 0 }, &mlp).unwrap();
+    assert_eq!(trained.parameters().unwrap().len(), nb_parameters);
+}
@@ -0,0 +1,52 @@
+use rl::mlp::MultiLayerPerceptron;
+use rl::trainer::cloning::{accuracy, BehaviourCloningTrainer, Demonstrations};
+use rl::trajectory::{Step, Trajectory};
+
+// Demonstrator going left below zero, right above, and waiting around zero
+fn demonstrations(n: usize) -> Demonstrations {
+    let mut t = Trajectory::new(None, vec![-1.0]);
+    for i in 1..=n {
+        let x = -1.0 + 2.0 * i as f32 / n as f32;
+        let prev = t.feature(i - 1)[0];
+        let action = match prev {
+            p if p < -0.2 => 0,
+            p if p > 0.2 => 2,
+            _ => 1,
+        };
+        t.steps.push(Step {
+            action,
+            reward: 0.0,
+            time_step: 0.1,
+            feature: vec![x],
+            terminated: false,
+            truncated: false,
+        });
+    }
+    Demonstrations::from_trajectories(&[t])
+}
+
+#[test]
+fn cloned_policy_imitates_the_demonstrator() {
+    let data = demonstrations(400);
+    assert_eq!(data.len(), 400);
+    assert_eq!((data.features[0][0], data.actions[0]), (-1.0, 0));
+    assert_eq!(data.action_counts(3), vec![160, 80, 160]);
+
+    let (train, validation) = data.split(0.25, Some(1));
+    assert_eq!((train.len(), validation.len()), (300, 100));
+
+    let trainer = BehaviourCloningTrainer {
+        epochs: 100,
+        learning_rate: 1e-2,
+        balance_actions: true,
+        seed: Some(0),
+        ..Default::default()
+    };
+    let policy: MultiLayerPerceptron<1, 3> = trainer.train(&train).unwrap();
+    let acc = accuracy(&policy, &validation).unwrap();
+    assert!(acc > 0.9, "validation accuracy {acc}");
+
+    // The demonstrations must fit the shape of the policy
+    assert!(trainer.train::<2, 3>(&train).is_err());
+    assert!(trainer.train::<1, 2>(&train).is_err());
+}
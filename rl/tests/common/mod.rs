@@ -0,0 +1,59 @@
+use candle_core::{Device, Tensor};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
+use std::error::Error;
+
+pub const LENGTH: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Move {
+    Wait,
+    Forward,
+}
+
+impl DiscreteAction for Move {
+    const COUNT: usize = 2;
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        [Move::Wait, Move::Forward].get(i).copied()
+    }
+}
+
+// Corridor whose exit is at the position LENGTH, every waiting step costing as much as a move
+#[derive(Clone)]
+pub struct Corridor {
+    pub pos: i32,
+}
+
+impl MarkovDecisionProcess for Corridor {
+    type Action = Move;
+
+    fn reset(&mut self) {
+        self.pos = 0;
+    }
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, action: Move, _: f32) -> Result<Transition, Box<dyn Error>> {
+        if action == Move::Forward {
+            self.pos += 1;
+        }
+        Ok(Transition::new(self.feature(), -1.0, self.is_finished()))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pos >= LENGTH
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[self.pos as f32 / LENGTH as f32], &Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![1.0])
+    }
+}
@@ -0,0 +1,131 @@
+use candle_core::{Device, Tensor};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::mlp::MultiLayerPerceptron;
+use rl::space::{BoxSpace, DiscreteAction};
+use rl::trainer::dqn::{huber_loss, DqnTrainer, Experience, ReplayBuffer};
+use std::error::Error;
+
+#[derive(Debug, PartialEq)]
+struct Stop;
+
+impl DiscreteAction for Stop {
+    const COUNT: usize = 1;
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        (i == 0).then_some(Stop)
+    }
+}
+
+// Game ending at its first step
+#[derive(Clone)]
+struct OneStep;
+
+impl MarkovDecisionProcess for OneStep {
+    type Action = Stop;
+
+    fn reset(&mut self) {}
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, _: Stop, _: f32) -> Result<Transition, Box<dyn Error>> {
+        Ok(Transition::new(self.feature(), 1.0, true))
+    }
+
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[0.0f32], &Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![1.0])
+    }
+}
+
+fn experience(reward: f32) -> Experience {
+    Experience {
+        feature: vec![reward, 0.0],
+        action: 1,
+        reward,
+        next_feature: vec![0.0, reward],
+        terminated: true,
+    }
+}
+
+#[test]
+fn replay_buffer_overwrites_the_oldest_experiences() {
+    let mut buffer = ReplayBuffer::new(3);
+    for r in 0..5 {
+        buffer.push(experience(r as f32));
+    }
+    assert_eq!(buffer.len(), 3);
+
+    // Only the rewards 2, 3 and 4 are left
+    let mut rng = rand::thread_rng();
+    let (x, a, r, x_next, terminated) = buffer.sample(16, &mut rng, &Device::Cpu).unwrap();
+    assert_eq!((x.dims(), x_next.dims()), (&[16, 2][..], &[16, 2][..]));
+    assert_eq!(a.to_vec1::<u32>().unwrap(), vec![1; 16]);
+    assert!(r.to_vec1::<f32>().unwrap().iter().all(|&r| r >= 2.0));
+    assert_eq!(terminated.to_vec1::<f32>().unwrap(), vec![1.0; 16]);
+}
+
+#[test]
+fn exploration_decays_linearly_then_stays() {
+    let trainer = DqnTrainer {
+        epsilon_start: 1.0,
+        epsilon_end: 0.2,
+        epsilon_decay_steps: 100,
+        ..Default::default()
+    };
+    for (step, epsilon) in [(0, 1.0), (50, 0.6), (100, 0.2), (1_000, 0.2)] {
+        assert!((trainer.epsilon(step) - epsilon).abs() < 1e-6);
+    }
+}
+
+#[test]
+fn huber_loss_is_quadratic_then_linear() {
+    let predictions = Tensor::new(&[0.0f32, 0.5, 3.0, -2.0], &Device::Cpu).unwrap();
+    let targets = Tensor::zeros(4, candle_core::DType::F32, &Device::Cpu).unwrap();
+
+    // 0, 0.5 * 0.5², 0.5 + (3 - 1) and 0.5 + (2 - 1)
+    let loss = huber_loss(&predictions, &targets).unwrap();
+    assert_eq!(loss.to_scalar::<f32>().unwrap(), (0.125 + 2.5 + 1.5) / 4.0);
+}
+
+// Name of a setting of the trainer and how to set it to zero
+type ZeroSetting = (&'static str, fn(&mut DqnTrainer));
+
+#[test]
+fn zero_sizes_and_periods_are_rejected() {
+    let trainer = DqnTrainer {
+        hidden_layers: vec![4],
+        learning_starts: 4,
+        batch_size: 4,
+        total_steps: 16,
+        ..Default::default()
+    };
+    let _: MultiLayerPerceptron<1, 1> = trainer.train(&OneStep).unwrap();
+
+    // Each of them would divide by zero or sample an empty batch
+    let zeros: [ZeroSetting; 5] = [
+        ("buffer_capacity", |t| t.buffer_capacity = 0),
+        ("batch_size", |t| t.batch_size = 0),
+        ("train_frequency", |t| t.train_frequency = 0),
+        ("target_update", |t| t.target_update = 0),
+        ("checkpoint_every", |t| t.checkpoint_every = 0),
+    ];
+    for (name, zero) in zeros {
+        let mut t = trainer.clone();
+        zero(&mut t);
+        match t.train::<_, 1, 1>(&OneStep) {
+            Err(e) => assert_eq!(e.to_string(), format!("{name} must be positive")),
+            Ok(_) => panic!("{name} = 0 is accepted"),
+        }
+    }
+}
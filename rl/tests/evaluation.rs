@@ -0,0 +1,152 @@
+use candle_core::Tensor;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use rl::ai::Agent;
+use rl::evaluation::{Episode, EvaluationSettings};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
+use rl::vec_env::VecEnv;
+use std::error::Error;
+
+#[derive(Debug, PartialEq)]
+struct Forward;
+
+impl DiscreteAction for Forward {
+    const COUNT: usize = 1;
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        (i == 0).then_some(Forward)
+    }
+}
+
+// Walk from a random start to the position 0, one step at a time
+#[derive(Clone)]
+struct Walk {
+    pos: u32,
+    rng: StdRng,
+}
+
+impl MarkovDecisionProcess for Walk {
+    type Action = Forward;
+
+    fn reset(&mut self) {
+        self.pos = self.rng.gen_range(1..50);
+    }
+
+    fn seed(&mut self, seed: u64) {
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
+    fn step(&mut self, _: Forward, _: f32) -> Result<Transition, Box<dyn Error>> {
+        self.pos -= 1;
+        Ok(Transition::new(self.feature(), -1.0, self.is_finished()))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pos == 0
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[self.pos as f32], &candle_core::Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![50.0])
+    }
+}
+
+struct Walker;
+
+impl Agent<Walk> for Walker {
+    fn policy(&self, _: &Walk) -> Result<Forward, Box<dyn Error>> {
+        Ok(Forward)
+    }
+}
+
+#[test]
+fn evaluation_plays_every_game_and_cuts_long_ones() {
+    let mut e = Walk {
+        pos: 0,
+        rng: StdRng::seed_from_u64(0),
+    };
+    let settings = EvaluationSettings {
+        nb_games: 20,
+        max_steps: 25,
+        seed: Some(7),
+        ..Default::default()
+    };
+    let report = Walker.evaluate(&mut e, &settings).unwrap();
+    assert_eq!(report.episodes.len(), 20);
+    for episode in &report.episodes {
+        assert!(episode.length <= 25);
+        assert_eq!(episode.total_reward, -(episode.length as f32));
+        assert!(episode.success || episode.length == 25);
+    }
+    assert!(0.0 < report.success_rate() && report.success_rate() < 1.0);
+    let (low, high) = report.confidence_interval();
+    assert!(low <= report.mean() && report.mean() <= high);
+
+    assert_eq!(Walker.evaluate_parallel(&e, &settings, 3).unwrap(), report);
+    let mut envs = VecEnv::new(vec![e.clone(); 4]);
+    assert_eq!(
+        Walker.evaluate_batched(&mut envs, &settings).unwrap(),
+        report
+    );
+}
+
+#[test]
+fn games_without_steps_are_cut_at_once() {
+    let e = Walk {
+        pos: 0,
+        rng: StdRng::seed_from_u64(0),
+    };
+    let settings = EvaluationSettings {
+        nb_games: 5,
+        max_steps: 0,
+        seed: Some(7),
+        ..Default::default()
+    };
+    let report = Walker.evaluate(&mut e.clone(), &settings).unwrap();
+    assert_eq!(report.episodes, vec![Episode::default(); 5]);
+    assert_eq!(Walker.evaluate_parallel(&e, &settings, 2).unwrap(), report);
+    let mut envs = VecEnv::new(vec![e; 2]);
+    assert_eq!(
+        Walker.evaluate_batched(&mut envs, &settings).unwrap(),
+        report
+    );
+}
+
+#[test]
+fn environments_without_a_game_are_not_stepped() {
+    // Stepping a walk at the position 0 would overflow
+    let e = Walk {
+        pos: 0,
+        rng: StdRng::seed_from_u64(0),
+    };
+    let settings = EvaluationSettings {
+        nb_games: 2,
+        max_steps: 100,
+        seed: Some(7),
+        ..Default::default()
+    };
+    let mut envs = VecEnv::new(vec![e.clone(); 4]);
+    let report = Walker.evaluate_batched(&mut envs, &settings).unwrap();
+    assert_eq!(report, Walker.evaluate(&mut e.clone(), &settings).unwrap());
+    assert!(envs.envs()[2..].iter().all(|e| e.pos == 0));
+}
+
+#[test]
+#[allow(deprecated)]
+fn play_game_returns_the_total_reward() {
+    let mut e = Walk {
+        pos: 0,
+        rng: StdRng::seed_from_u64(0),
+    };
+    e.reset();
+    let start = e.pos;
+    assert_eq!(Walker.play_game(&mut e, None).unwrap(), -(start as f32));
+    assert_eq!(Walker.play_game(&mut e, None).unwrap(), 0.0);
+}
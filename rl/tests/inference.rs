@@ -0,0 +1,144 @@
+use candle_core::{Device, Tensor};
+use rl::ai::{Agent, InferenceMode, ModalAgent};
+use rl::evaluation::{EvaluationReport, EvaluationSettings};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
+use std::error::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Choice(usize);
+
+impl DiscreteAction for Choice {
+    const COUNT: usize = 3;
+
+    fn index(&self) -> usize {
+        self.0
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        (i < Self::COUNT).then_some(Choice(i))
+    }
+}
+
+// Game whose single state never changes, each action being rewarded with its index
+#[derive(Clone)]
+struct Stay;
+
+impl MarkovDecisionProcess for Stay {
+    type Action = Choice;
+
+    fn reset(&mut self) {}
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, a: Choice, _: f32) -> Result<Transition, Box<dyn Error>> {
+        Ok(Transition::new(self.feature(), a.0 as f32, false))
+    }
+
+    fn is_finished(&self) -> bool {
+        false
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[0.0f32], &Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![0.0])
+    }
+}
+
+// Agent whose greedy action is the last one
+struct Skewed;
+
+impl Agent<Stay> for Skewed {
+    fn policy(&self, _: &Stay) -> Result<Choice, Box<dyn Error>> {
+        Ok(Choice(2))
+    }
+
+    fn action_distribution(&self, _: &Stay) -> Result<Vec<f32>, Box<dyn Error>> {
+        Ok(vec![0.2, 0.3, 0.5])
+    }
+}
+
+struct Constant;
+
+impl Agent<Stay> for Constant {
+    fn policy(&self, _: &Stay) -> Result<Choice, Box<dyn Error>> {
+        Ok(Choice(1))
+    }
+}
+
+fn frequencies(agent: &ModalAgent<Skewed>, n: usize) -> Vec<f32> {
+    let mut counts = vec![0; 3];
+    for _ in 0..n {
+        counts[agent.policy(&Stay).unwrap().0] += 1;
+    }
+    counts.into_iter().map(|c| c as f32 / n as f32).collect()
+}
+
+#[test]
+fn deterministic_agents_put_all_the_mass_on_their_action() {
+    assert_eq!(
+        Constant.action_distribution(&Stay).unwrap(),
+        [0.0, 1.0, 0.0]
+    );
+    let greedy = ModalAgent::new(Skewed, InferenceMode::Greedy, Some(0));
+    assert_eq!(greedy.action_distribution(&Stay).unwrap(), [0.0, 0.0, 1.0]);
+    assert_eq!(frequencies(&greedy, 100), [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn sampled_actions_follow_the_distribution() {
+    let sampled = ModalAgent::new(Skewed, InferenceMode::Sampled, Some(1));
+    for (f, p) in frequencies(&sampled, 10_000).iter().zip([0.2, 0.3, 0.5]) {
+        assert!((f - p).abs() < 0.03, "{f} instead of {p}");
+    }
+
+    // The same seed plays the same actions
+    let play = |seed| {
+        let agent = ModalAgent::new(Skewed, InferenceMode::Sampled, Some(seed));
+        (0..50)
+            .map(|_| agent.policy(&Stay).unwrap())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(play(4), play(4));
+}
+
+#[test]
+fn epsilon_greedy_explores_uniformly() {
+    let agent = ModalAgent::new(Skewed, InferenceMode::EpsilonGreedy(0.3), Some(2));
+    let expected = [0.1, 0.1, 0.8];
+    for (p, e) in agent
+        .action_distribution(&Stay)
+        .unwrap()
+        .iter()
+        .zip(expected)
+    {
+        assert!((p - e).abs() < 1e-6);
+    }
+    for (f, p) in frequencies(&agent, 10_000).iter().zip(expected) {
+        assert!((f - p).abs() < 0.03, "{f} instead of {p}");
+    }
+}
+
+#[test]
+fn sampled_evaluations_do_not_depend_on_the_threads() {
+    let agent = ModalAgent::new(Skewed, InferenceMode::Sampled, Some(5));
+    let settings = EvaluationSettings {
+        nb_games: 8,
+        max_steps: 20,
+        seed: Some(6),
+        ..Default::default()
+    };
+    let returns =
+        |r: EvaluationReport| -> Vec<f32> { r.episodes.iter().map(|e| e.total_reward).collect() };
+    let expected = returns(agent.evaluate(&mut Stay, &settings).unwrap());
+    assert!(expected.iter().any(|&r| r != expected[0]));
+    for nb_threads in [1, 3, 8] {
+        let report = agent
+            .evaluate_parallel(&Stay, &settings, nb_threads)
+            .unwrap();
+        assert_eq!(returns(report), expected);
+    }
+}
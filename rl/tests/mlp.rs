@@ -0,0 +1,50 @@
+use candle_core::{safetensors, DType, Device, Module, Tensor};
+use candle_nn::{VarBuilder, VarMap};
+use rl::error::{self, LoadError};
+use rl::mlp::MultiLayerPerceptron;
+use rl::normalization::RunningMeanStd;
+
+#[test]
+fn saved_perceptron_is_reloaded() {
+    let varmap = VarMap::new();
+    let vs = VarBuilder::from_varmap(&varmap, DType::F32, &Device::Cpu);
+    let mut mlp = MultiLayerPerceptron::<2, 3>::new(vs, &[4, 8]).unwrap();
+    let mut stats = RunningMeanStd::new(2);
+    stats.update(&[0.5, 0.1]);
+    stats.update(&[1.5, -0.1]);
+    mlp.obs_norm = Some(stats);
+    let path = std::env::temp_dir().join("rl_saved_perceptron_is_reloaded.safetensors");
+    mlp.save(&path).unwrap();
+
+    let mut h = safetensors::load(&path, &Device::Cpu).unwrap();
+    let loaded = MultiLayerPerceptron::<2, 3>::try_from(&mut h).unwrap();
+    let x = Tensor::new(&[[0.5f32, -0.1], [1.2, 0.3]], &Device::Cpu).unwrap();
+    let expected = mlp.forward(&x).unwrap().to_vec2::<f32>().unwrap();
+    assert_eq!(
+        loaded.forward(&x).unwrap().to_vec2::<f32>().unwrap(),
+        expected
+    );
+    assert_eq!(loaded.obs_norm, mlp.obs_norm);
+    assert_eq!(
+        error::model_kind(&path).unwrap().as_deref(),
+        Some(rl::mlp::MODEL_KIND)
+    );
+
+    let mut h = safetensors::load(&path, &Device::Cpu).unwrap();
+    let err = MultiLayerPerceptron::<5, 3>::try_from(&mut h)
+        .err()
+        .unwrap();
+    assert!(
+        matches!(&err, LoadError::ShapeMismatch { name, .. } if name == "metadata.input_size"),
+        "{err}"
+    );
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn perceptrons_need_non_empty_internal_layers() {
+    for sizes in [&[][..], &[4, 0]] {
+        assert!(MultiLayerPerceptron::<2, 3>::random(sizes).is_err());
+    }
+    assert!(MultiLayerPerceptron::<2, 3>::random(&[4]).is_ok());
+}
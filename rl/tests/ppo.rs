@@ -0,0 +1,103 @@
+mod common;
+
+use candle_core::{safetensors, Device, Module, Tensor};
+use common::Corridor;
+use rl::mlp::MultiLayerPerceptron;
+use rl::trainer::ppo::{clipped_objective, ActorCritic, PpoTrainer};
+
+#[test]
+fn advantages_decay_until_the_end_of_the_games() {
+    let trainer = PpoTrainer {
+        discount: 0.5,
+        gae_lambda: 0.5,
+        ..Default::default()
+    };
+
+    // Two environments, the first one finishing a game at its second step
+    let rewards = [1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+    let values = [0.5, 0.0, 0.5, 0.0, 2.0, 0.0];
+    let dones = [false, false, true, false, false, false];
+    let (advantages, returns) = trainer.advantages(&rewards, &values, &dones, &[4.0, 0.0]);
+
+    // δ = (0.75, 0.5, 1) on the first environment, and the advantage of its first step is
+    // 0.75 + 0.25 * 0.5
+    assert_eq!(advantages, [0.875, 0.0, 0.5, 0.0, 1.0, 0.0]);
+    assert_eq!(returns, [1.375, 0.0, 1.0, 0.0, 3.0, 0.0]);
+}
+
+#[test]
+fn objective_is_pessimistic_about_the_ratios() {
+    let ratio = Tensor::new(&[0.5f32, 1.5, 1.5, 0.5], &Device::Cpu).unwrap();
+    let advantages = Tensor::new(&[1.0f32, 1.0, -1.0, -1.0], &Device::Cpu).unwrap();
+
+    // 0.5, 1.2 once clipped, -1.5 and -0.8 once clipped
+    let objective = clipped_objective(&ratio, &advantages, 0.2).unwrap();
+    assert!((objective.to_scalar::<f32>().unwrap() + 0.15).abs() < 1e-6);
+}
+
+#[test]
+fn critic_is_checkpointed_next_to_the_actor() {
+    let path = std::env::temp_dir().join("rl_ppo_critic_is_checkpointed.safetensors");
+    let trainer = PpoTrainer {
+        hidden_layers: vec![8],
+        rollout_steps: 16,
+        minibatch_size: 16,
+        nb_envs: 2,
+        total_steps: 64,
+        checkpoint: Some(path.clone()),
+        seed: Some(3),
+        ..Default::default()
+    };
+    let model: ActorCritic<1, 2> = trainer.train(&Corridor { pos: 0 }).unwrap();
+
+    // The critic file is a perceptron file with one output
+    let critic_path = ActorCritic::<1, 2>::critic_path(&path);
+    let mut h = safetensors::load(&critic_path, &Device::Cpu).unwrap();
+    let critic = MultiLayerPerceptron::<1, 1>::try_from(&mut h).unwrap();
+    let x = Tensor::new(&[[0.4f32]], &Device::Cpu).unwrap();
+    assert_eq!(
+        model.values(&x).unwrap().to_vec1::<f32>().unwrap(),
+        critic
+            .forward(&x)
+            .unwrap()
+            .flatten_all()
+            .unwrap()
+            .to_vec1::<f32>()
+            .unwrap()
+    );
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(critic_path).unwrap();
+}
+
+// Name of a setting of the trainer and how to set it to zero
+type ZeroSetting = (&'static str, fn(&mut PpoTrainer));
+
+#[test]
+fn zero_sizes_and_periods_are_rejected() {
+    let trainer = PpoTrainer {
+        hidden_layers: vec![4],
+        rollout_steps: 4,
+        minibatch_size: 4,
+        nb_envs: 1,
+        total_steps: 8,
+        ..Default::default()
+    };
+    let _: ActorCritic<1, 2> = trainer.train(&Corridor { pos: 0 }).unwrap();
+
+    // Each of them would divide by zero or never update the networks
+    let zeros: [ZeroSetting; 5] = [
+        ("rollout_steps", |t| t.rollout_steps = 0),
+        ("nb_epochs", |t| t.nb_epochs = 0),
+        ("minibatch_size", |t| t.minibatch_size = 0),
+        ("nb_envs", |t| t.nb_envs = 0),
+        ("checkpoint_every", |t| t.checkpoint_every = 0),
+    ];
+    for (name, zero) in zeros {
+        let mut t = trainer.clone();
+        zero(&mut t);
+        match t.train::<_, 1, 2>(&Corridor { pos: 0 }) {
+            Err(e) => assert_eq!(e.to_string(), format!("{name} must be positive")),
+            Ok(_) => panic!("{name} = 0 is accepted"),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use common::Corridor;
+use rl::mlp::MultiLayerPerceptron;
+use rl::trainer::reinforce::{ReinforceTrainer, Trajectory};
+
+#[test]
+fn returns_are_discounted_from_each_step() {
+    let mut trajectory = Trajectory {
+        features: vec![vec![0.0]; 3],
+        actions: vec![0; 3],
+        rewards: vec![1.0, 2.0, 4.0],
+        last_feature: vec![0.0],
+        terminated: true,
+    };
+    assert_eq!(trajectory.returns(0.5, 8.0), [3.0, 4.0, 4.0]);
+    assert_eq!(trajectory.total_reward(), 7.0);
+
+    // Cut games continue with the value of the last state
+    trajectory.terminated = false;
+    assert_eq!(trajectory.returns(0.5, 8.0), [4.0, 6.0, 8.0]);
+}
+
+// Name of a setting of the trainer and how to set it to zero
+type ZeroSetting = (&'static str, fn(&mut ReinforceTrainer));
+
+#[test]
+fn zero_sizes_and_periods_are_rejected() {
+    let trainer = ReinforceTrainer {
+        hidden_layers: vec![4],
+        batch_episodes: 2,
+        nb_updates: 2,
+        max_episode_steps: 10,
+        ..Default::default()
+    };
+    let _: MultiLayerPerceptron<1, 2> = trainer.train(&mut Corridor { pos: 0 }).unwrap();
+
+    // Each of them would average over no steps or divide by zero
+    let zeros: [ZeroSetting; 3] = [
+        ("batch_episodes", |t| t.batch_episodes = 0),
+        ("max_episode_steps", |t| t.max_episode_steps = 0),
+        ("checkpoint_every", |t| t.checkpoint_every = 0),
+    ];
+    for (name, zero) in zeros {
+        let mut t = trainer.clone();
+        zero(&mut t);
+        match t.train::<_, 1, 2>(&mut Corridor { pos: 0 }) {
+            Err(e) => assert_eq!(e.to_string(), format!("{name} must be positive")),
+            Ok(_) => panic!("{name} = 0 is accepted"),
+        }
+    }
+}
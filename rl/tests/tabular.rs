@@ -0,0 +1,118 @@
+use candle_core::{Device, Tensor};
+use rl::ai::Agent;
+use rl::discretizer::Discretizer;
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::space::{BoxSpace, DiscreteAction};
+use rl::tabular::Tabular;
+use rl::trainer::tabular::{QTable, TabularTrainer, TdMethod};
+use std::error::Error;
+
+const LENGTH: i32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Move {
+    Wait,
+    Forward,
+}
+
+impl DiscreteAction for Move {
+    const COUNT: usize = 2;
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        [Move::Wait, Move::Forward].get(i).copied()
+    }
+}
+
+// Corridor whose exit is at the position LENGTH, every step costing one
+struct Corridor {
+    pos: i32,
+}
+
+impl MarkovDecisionProcess for Corridor {
+    type Action = Move;
+
+    fn reset(&mut self) {
+        self.pos = 0;
+    }
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, action: Move, _: f32) -> Result<Transition, Box<dyn Error>> {
+        if action == Move::Forward {
+            self.pos += 1;
+        }
+        Ok(Transition::new(self.feature(), -1.0, self.is_finished()))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.pos >= LENGTH
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[self.pos as f32 / LENGTH as f32], &Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![1.0])
+    }
+}
+
+#[test]
+fn q_learning_updates_the_visited_cells() {
+    // Greedy with ties going to Wait: the agent waits twice, since the next action is picked
+    // before the update, then moves forward
+    let trainer = TabularTrainer {
+        method: TdMethod::QLearning,
+        learning_rate: 0.5,
+        discount: 0.9,
+        epsilon: 0.0,
+        nb_episodes: 1,
+        max_steps: 3,
+        seed: Some(0),
+        ..Default::default()
+    };
+    let mut e = Corridor { pos: 0 };
+    let q = trainer
+        .train(&mut e, LENGTH as usize + 1, |f| {
+            Ok((f.to_vec1::<f32>()?[0] * LENGTH as f32).round() as usize)
+        })
+        .unwrap();
+    assert_eq!((q.nb_states(), q.nb_actions()), (6, 2));
+
+    // Q(0, Wait) = 0.5 * (-1 + 0.9 * 0) = -0.5, then -0.5 + 0.5 * (-1 + 0.9 * 0 - -0.5) = -0.75
+    // Q(0, Forward) = 0.5 * (-1 + 0.9 * max Q(1)) = -0.5
+    assert_eq!(q.row(0), [-0.75, -0.5]);
+    assert!((1..6).all(|s| q.row(s) == [0.0, 0.0]));
+    assert_eq!(q.greedy(0), 1);
+}
+
+#[test]
+fn invalid_grids_and_tables_are_rejected() {
+    // Missing bound, empty dimension and reversed bounds
+    assert!(Discretizer::new(vec![0.0], vec![1.0, 1.0], vec![2, 2]).is_err());
+    assert!(Discretizer::new(vec![0.0, 0.0], vec![1.0, 1.0], vec![2, 0]).is_err());
+    assert!(Discretizer::new(vec![0.0, 1.0], vec![1.0, 1.0], vec![2, 2]).is_err());
+    assert!(Discretizer::new(vec![0.0], vec![f32::INFINITY], vec![2]).is_err());
+
+    let no_action = Tensor::zeros((4, 0), candle_core::DType::F32, &Device::Cpu).unwrap();
+    assert!(QTable::from_tensor(&no_action).is_err());
+}
+
+#[test]
+fn tables_must_match_the_game() {
+    let e = Corridor { pos: 2 };
+    let grid = Discretizer::new(vec![0.0], vec![1.0], vec![5]).unwrap();
+    let tabular = Tabular::new(QTable::new(5, 2, 0.0), grid.clone()).unwrap();
+    assert_eq!(tabular.policy(&e).unwrap(), Move::Wait);
+
+    // A column per action of the game, and a grid over its features
+    let three_actions = Tabular::new(QTable::new(5, 3, 0.0), grid).unwrap();
+    assert!(Agent::<Corridor>::policy(&three_actions, &e).is_err());
+    let grid = Discretizer::new(vec![0.0, 0.0], vec![1.0, 1.0], vec![5, 1]).unwrap();
+    let two_features = Tabular::new(QTable::new(5, 2, 0.0), grid).unwrap();
+    assert!(two_features.policy(&e).is_err());
+}
@@ -0,0 +1,194 @@
+use candle_core::{DType, Device, Tensor};
+use candle_nn::{VarBuilder, VarMap};
+use rl::mdp::{MarkovDecisionProcess, Transition};
+use rl::mlp::MultiLayerPerceptron;
+use rl::normalization::RunningMeanStd;
+use rl::space::{BoxSpace, DiscreteAction};
+use rl::trajectory;
+use rl::wrappers::{
+    FrameSkip, NormalizeObservation, RecordEpisodeStatistics, ScaleReward, TimeLimit,
+    TrajectoryRecorder,
+};
+use std::error::Error;
+
+#[derive(Debug, PartialEq)]
+struct Forward;
+
+impl DiscreteAction for Forward {
+    const COUNT: usize = 1;
+
+    fn index(&self) -> usize {
+        0
+    }
+
+    fn from_index(i: usize) -> Option<Self> {
+        (i == 0).then_some(Forward)
+    }
+}
+
+// Walk from the position 10 to the position 0, one step at a time
+struct Walk(u32);
+
+impl MarkovDecisionProcess for Walk {
+    type Action = Forward;
+
+    fn reset(&mut self) {
+        self.0 = 10;
+    }
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, _: Forward, _: f32) -> Result<Transition, Box<dyn Error>> {
+        self.0 -= 1;
+        Ok(Transition::new(self.feature(), -1.0, self.is_finished()))
+    }
+
+    fn is_finished(&self) -> bool {
+        self.0 == 0
+    }
+
+    fn feature(&self) -> Tensor {
+        Tensor::new(&[self.0 as f32], &candle_core::Device::Cpu).unwrap()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0], vec![10.0])
+    }
+}
+
+// Walk declaring two features but giving only its position
+struct Misdeclared(Walk);
+
+impl MarkovDecisionProcess for Misdeclared {
+    type Action = Forward;
+
+    fn reset(&mut self) {
+        self.0.reset();
+    }
+
+    fn seed(&mut self, _: u64) {}
+
+    fn step(&mut self, a: Forward, time_step: f32) -> Result<Transition, Box<dyn Error>> {
+        self.0.step(a, time_step)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.0.is_finished()
+    }
+
+    fn feature(&self) -> Tensor {
+        self.0.feature()
+    }
+
+    fn observation_space(&self) -> BoxSpace {
+        BoxSpace::new(vec![0.0; 2], vec![10.0; 2])
+    }
+}
+
+fn play<T: MarkovDecisionProcess<Action = Forward>>(e: &mut T) -> Vec<Transition> {
+    e.reset();
+    let mut transitions = vec![e.step(Forward, 0.1).unwrap()];
+    while !transitions.last().unwrap().is_done() {
+        transitions.push(e.step(Forward, 0.1).unwrap());
+    }
+    transitions
+}
+
+#[test]
+fn wrappers_compose() {
+    let mut e = RecordEpisodeStatistics::new(TimeLimit::new(FrameSkip::new(Walk(10), 3), 2));
+    let transitions = play(&mut e);
+    assert_eq!(transitions.len(), 2);
+    assert!(transitions[1].truncated && !transitions[1].terminated);
+    assert_eq!(transitions[1].info["episode.return"], -6.0);
+    assert_eq!(e.0, 4);
+
+    let mut e = RecordEpisodeStatistics::new(FrameSkip::new(Walk(10), 4));
+    let transitions = play(&mut e);
+    assert_eq!(transitions.iter().map(|t| t.reward).sum::<f32>(), -10.0);
+    let episode = e.last_episode().unwrap();
+    assert_eq!((episode.length, episode.success), (3, true));
+}
+
+#[test]
+fn trajectories_are_saved_and_loaded() {
+    let mut e = TrajectoryRecorder::new(TimeLimit::new(Walk(10), 4));
+    e.seed(7);
+    play(&mut e);
+    play(&mut e);
+    e.reset();
+
+    let trajectories = e.trajectories();
+    assert_eq!(trajectories.len(), 2);
+    assert_eq!(trajectories[0].seed, Some(7));
+    assert_eq!(trajectories[1].seed, None);
+    let t = &trajectories[0];
+    assert_eq!((t.len(), t.total_reward()), (4, -4.0));
+    assert_eq!(t.feature(0), &[10.0]);
+    assert_eq!(t.feature(4), &[6.0]);
+    assert!(t.is_done() && t.steps[3].truncated);
+    assert!((t.times()[4] - 0.4).abs() < 1e-6);
+
+    let mut file = Vec::new();
+    trajectory::write(&trajectories, &mut file).unwrap();
+    assert_eq!(String::from_utf8_lossy(&file).lines().count(), 10);
+    assert_eq!(trajectory::read(file.as_slice()).unwrap(), trajectories);
+    assert!(trajectory::read(&file[file.len() / 2..]).is_err());
+}
+
+#[test]
+fn normalization_statistics_are_given_to_the_perceptron() {
+    // The positions 9 to 0 have a mean of 4.5 and a variance of 8.25
+    let mut e = NormalizeObservation::new(Walk(10));
+    let transitions = play(&mut e);
+    assert!((e.stats().mean[0] - 4.5).abs() < 1e-3);
+    assert!((e.stats().var[0] - 8.25).abs() < 1e-3);
+    let last = transitions
+        .last()
+        .unwrap()
+        .feature
+        .to_vec1::<f32>()
+        .unwrap();
+    assert!((last[0] + 4.5 / 8.25f32.sqrt()).abs() < 1e-3);
+
+    let varmap = VarMap::new();
+    let vs = VarBuilder::from_varmap(&varmap, DType::F32, &Device::Cpu);
+    let mut mlp = MultiLayerPerceptron::<1, 1>::new(vs, &[4]).unwrap();
+    e.export_stats(&mut mlp).unwrap();
+    assert!(!e.training);
+    e.reset();
+    assert_eq!(
+        mlp.normalize(&Walk(10).feature())
+            .unwrap()
+            .to_vec1::<f32>()
+            .unwrap(),
+        e.feature().to_vec1::<f32>().unwrap()
+    );
+
+    // The features would be normalized twice
+    assert!(e.export_stats(&mut mlp).is_err());
+    assert!(NormalizeObservation::with_stats(Walk(10), RunningMeanStd::new(2)).is_err());
+}
+
+#[test]
+#[should_panic(expected = "Cannot normalize the observation")]
+fn mismatched_features_are_not_returned_raw() {
+    let mut e = NormalizeObservation::new(Misdeclared(Walk(10)));
+    assert!(e.step(Forward, 0.1).is_err());
+    e.feature();
+}
+
+#[test]
+fn rewards_are_scaled_by_the_deviation_of_the_return() {
+    // The undiscounted returns -1 to -10 have a variance of 8.25
+    let mut e = ScaleReward::new(Walk(10), 1.0);
+    let transitions = play(&mut e);
+    let last = transitions.last().unwrap().reward;
+    assert!((last + 1.0 / 8.25f32.sqrt()).abs() < 1e-3);
+
+    // The return starts again from -1 and not -11, which moves the variance to
+    // (82.5 + 202.5 / 11) / 11 rather than 10
+    let first = play(&mut e)[0].reward;
+    let var = (82.5 + 202.5 / 11.0) / 11.0f32;
+    assert!((first + 1.0 / var.sqrt()).abs() < 1e-3, "{first}");
+}
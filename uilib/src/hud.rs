@@ -0,0 +1,373 @@
+use crate::{despawn_screen, GameState, Replay};
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use rl::mdp::MarkovDecisionProcess;
+use rl::wrappers::TrajectoryRecorder;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+
+// Duration of the game shown by the charts, in seconds
+const HUD_WINDOW: f32 = 10.0;
+
+// Size of a chart, margin around it and distance of the first one to the top of the window
+const CHART_SIZE: Vec2 = Vec2::new(360.0, 160.0);
+const CHART_MARGIN: f32 = 20.0;
+const CHART_TOP: f32 = 80.0;
+
+const BACKGROUND_COLOR: Color = Color::rgba(1.0, 1.0, 1.0, 0.6);
+const LINE_COLOR: Color = Color::rgb(0.8, 0.2, 0.2);
+const ZERO_COLOR: Color = Color::GRAY;
+
+/// Resource holding the game displayed by the HUD.
+pub trait HudSource: Resource {
+    /// Markov decision process being played.
+    type Game: MarkovDecisionProcess;
+
+    /// Game being played, recording its steps.
+    fn recorder(&self) -> &TrajectoryRecorder<Self::Game>;
+}
+
+/// Quantity plotted along an axis of a chart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum HudAxis {
+    /// Time elapsed since the start of the episode, in seconds.
+    Time,
+
+    /// Feature of the state, by its index in the flattened feature tensor.
+    Feature(usize),
+
+    /// Reward collected since the start of the episode.
+    Reward,
+}
+
+/// Line chart of the HUD, plotting a quantity against another one over the last seconds of
+/// the game.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HudChart {
+    /// Title displayed above the chart.
+    pub title: &'static str,
+
+    /// Quantity along the horizontal axis.
+    pub x: HudAxis,
+
+    /// Quantity along the vertical axis.
+    pub y: HudAxis,
+}
+
+impl HudChart {
+    /// Chart of the quantity against the time.
+    pub fn over_time(title: &'static str, y: HudAxis) -> Self {
+        HudChart {
+            title,
+            x: HudAxis::Time,
+            y,
+        }
+    }
+}
+
+/// State of the game at one step, as seen by the HUD.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HudSample {
+    /// Time elapsed since the start of the episode, in seconds.
+    pub time: f32,
+
+    /// Flattened features of the state.
+    pub feature: Vec<f32>,
+
+    /// Reward collected since the start of the episode.
+    pub reward: f32,
+}
+
+impl HudSample {
+    fn value(&self, axis: HudAxis) -> f32 {
+        match axis {
+            HudAxis::Time => self.time,
+            HudAxis::Feature(i) => self.feature.get(i).copied().unwrap_or_default(),
+            HudAxis::Reward => self.reward,
+        }
+    }
+}
+
+/// Samples of the last seconds of the game, and visibility of the charts kept from one game to
+/// the next.
+#[derive(Resource, Debug)]
+pub struct Hud {
+    /// The charts are displayed.
+    pub visible: bool,
+
+    samples: VecDeque<HudSample>,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Hud {
+            visible: true,
+            samples: VecDeque::new(),
+        }
+    }
+}
+
+impl Hud {
+    /// Samples of the last seconds, the oldest first.
+    pub fn samples(&self) -> impl Iterator<Item = &HudSample> {
+        self.samples.iter()
+    }
+
+    /// Add the sample, forgetting the ones that left the time window. The charts start again
+    /// when the time goes back, e.g. on a new episode.
+    pub fn push(&mut self, sample: HudSample) {
+        match self.samples.back() {
+            Some(last) if sample.time < last.time => self.samples.clear(),
+            Some(last) if sample.time == last.time => return,
+            _ => {}
+        }
+        while self
+            .samples
+            .front()
+            .is_some_and(|s| s.time < sample.time - HUD_WINDOW)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    /// Forget the samples.
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    // Bounds of the axis: the time window, the bounds of the feature if finite, or else the
+    // range of the samples
+    fn range(&self, axis: HudAxis, low: &[f32], high: &[f32]) -> (f32, f32) {
+        let (lo, hi) = match axis {
+            HudAxis::Time => {
+                let last = self.samples.back().map_or(0.0, |s| s.time);
+                let start = (last - HUD_WINDOW).max(0.0);
+                return (start, start + HUD_WINDOW);
+            }
+            HudAxis::Feature(i) => match (low.get(i), high.get(i)) {
+                (Some(&lo), Some(&hi)) if lo.is_finite() && hi.is_finite() && lo < hi => {
+                    return (lo, hi)
+                }
+                _ => self.data_range(axis),
+            },
+            HudAxis::Reward => self.data_range(axis),
+        };
+        if hi - lo < 1e-3 {
+            (lo - 1.0, hi + 1.0)
+        } else {
+            let pad = 0.05 * (hi - lo);
+            (lo - pad, hi + pad)
+        }
+    }
+
+    fn data_range(&self, axis: HudAxis) -> (f32, f32) {
+        self.samples
+            .iter()
+            .map(|s| s.value(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(lo, hi), v| {
+                (lo.min(v), hi.max(v))
+            })
+    }
+}
+
+/// Struct implementing the Plugin trait. Draws the charts in a column on the left of the
+/// window while the game is played, the P key showing and hiding them.
+pub struct HudPlugin<W: HudSource> {
+    /// Charts displayed, from top to bottom.
+    pub charts: Vec<HudChart>,
+
+    source: PhantomData<W>,
+}
+
+impl<W: HudSource> HudPlugin<W> {
+    /// Plot the charts of the game held by the resource `W`.
+    pub fn new(charts: Vec<HudChart>) -> Self {
+        HudPlugin {
+            charts,
+            source: PhantomData,
+        }
+    }
+}
+
+#[derive(Resource)]
+struct HudCharts(Vec<HudChart>);
+
+// Chart drawn by the entity, by its index
+#[derive(Component)]
+struct HudPanel(usize);
+
+impl<W: HudSource> Plugin for HudPlugin<W> {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Hud>()
+            .insert_resource(HudCharts(self.charts.clone()))
+            .add_systems(OnEnter(GameState::Playing), spawn_hud)
+            .add_systems(
+                FixedPostUpdate,
+                record_hud::<W>
+                    .run_if(in_state(GameState::Playing))
+                    .run_if(resource_exists::<W>),
+            )
+            .add_systems(
+                Update,
+                (switch_hud, draw_hud::<W>.run_if(resource_exists::<W>))
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                (despawn_screen::<HudPanel>, clear_hud),
+            );
+    }
+}
+
+fn visibility(visible: bool) -> Visibility {
+    if visible {
+        Visibility::Inherited
+    } else {
+        Visibility::Hidden
+    }
+}
+
+// Center of the chart in the world, the camera being centered on the window
+fn chart_center(window: &Window, i: usize) -> Vec2 {
+    Vec2::new(
+        -window.width() / 2.0 + CHART_MARGIN + CHART_SIZE.x / 2.0,
+        window.height() / 2.0
+            - CHART_TOP
+            - CHART_SIZE.y / 2.0
+            - i as f32 * (CHART_SIZE.y + 2.0 * CHART_MARGIN),
+    )
+}
+
+fn spawn_hud(
+    mut commands: Commands,
+    charts: Res<HudCharts>,
+    hud: Res<Hud>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+) {
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    for (i, chart) in charts.0.iter().enumerate() {
+        commands
+            .spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        color: BACKGROUND_COLOR,
+                        custom_size: Some(CHART_SIZE),
+                        ..default()
+                    },
+                    transform: Transform::from_translation(chart_center(window, i).extend(5.0)),
+                    visibility: visibility(hud.visible),
+                    ..default()
+                },
+                HudPanel(i),
+            ))
+            .with_children(|parent| {
+                parent.spawn(Text2dBundle {
+                    text: Text::from_section(
+                        chart.title,
+                        TextStyle {
+                            font_size: 16.0,
+                            color: Color::BLACK,
+                            ..default()
+                        },
+                    ),
+                    transform: Transform::from_xyz(0.0, CHART_SIZE.y / 2.0 + 10.0, 0.0),
+                    ..default()
+                });
+            });
+    }
+}
+
+// Sample the game after its step, or the replay at its current time
+fn record_hud<W: HudSource>(mut hud: ResMut<Hud>, source: Res<W>, replay: Option<Res<Replay>>) {
+    let sample = match replay {
+        Some(r) => HudSample {
+            time: r.time,
+            feature: r.feature().to_vec(),
+            reward: r.trajectory().steps[..r.step()]
+                .iter()
+                .map(|s| s.reward)
+                .sum(),
+        },
+        None => {
+            let m = source.recorder();
+            let t = m.current_trajectory();
+            match m.feature().flatten_all().and_then(|f| f.to_vec1::<f32>()) {
+                Ok(feature) => HudSample {
+                    time: t.duration(),
+                    feature,
+                    reward: t.total_reward(),
+                },
+                Err(e) => {
+                    error!("The features of the game could not be read: {e}");
+                    return;
+                }
+            }
+        }
+    };
+    hud.push(sample);
+}
+
+/// Show and hide the charts with the P key.
+fn switch_hud(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut hud: ResMut<Hud>,
+    mut panels: Query<&mut Visibility, With<HudPanel>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyP) {
+        hud.visible = !hud.visible;
+        for mut v in &mut panels {
+            *v = visibility(hud.visible);
+        }
+    }
+}
+
+fn draw_hud<W: HudSource>(
+    mut gizmos: Gizmos,
+    hud: Res<Hud>,
+    charts: Res<HudCharts>,
+    source: Res<W>,
+    panels: Query<(&HudPanel, &Transform)>,
+) {
+    if !hud.visible || hud.samples.is_empty() {
+        return;
+    }
+    let space = source.recorder().observation_space();
+    for (panel, transform) in &panels {
+        let chart = &charts.0[panel.0];
+        let center = transform.translation.truncate();
+        let (x0, x1) = hud.range(chart.x, &space.low, &space.high);
+        let (y0, y1) = hud.range(chart.y, &space.low, &space.high);
+        let to_chart = |x: f32, y: f32| {
+            let unit = Vec2::new((x - x0) / (x1 - x0), (y - y0) / (y1 - y0));
+            center + (unit.clamp(Vec2::ZERO, Vec2::ONE) - 0.5) * CHART_SIZE
+        };
+
+        gizmos.rect_2d(center, 0.0, CHART_SIZE, Color::BLACK);
+        if x0 < 0.0 && 0.0 < x1 {
+            gizmos.line_2d(to_chart(0.0, y0), to_chart(0.0, y1), ZERO_COLOR);
+        }
+        if y0 < 0.0 && 0.0 < y1 {
+            gizmos.line_2d(to_chart(x0, 0.0), to_chart(x1, 0.0), ZERO_COLOR);
+        }
+        gizmos.linestrip_2d(
+            hud.samples()
+                .map(|s| to_chart(s.value(chart.x), s.value(chart.y))),
+            LINE_COLOR,
+        );
+        if let Some(last) = hud.samples.back() {
+            gizmos.circle_2d(
+                to_chart(last.value(chart.x), last.value(chart.y)),
+                4.0,
+                Color::BLACK,
+            );
+        }
+    }
+}
+
+fn clear_hud(mut hud: ResMut<Hud>) {
+    hud.clear();
+}
@@ -2,11 +2,18 @@
 //! Hello
 //!
 use bevy::prelude::*;
-pub use menu::{ButtonColors, Customization, MenuPlugin};
-use rl::{ai::Agent, mdp::MarkovDecisionProcess};
+pub use hud::{Hud, HudAxis, HudChart, HudPlugin, HudSample, HudSource};
+pub use menu::{ButtonColors, Customization, MenuMessage, MenuPlugin};
+use rand::{rngs::StdRng, SeedableRng};
+pub use replay::{save_trajectories, Replay, DEMONSTRATION_DIR, TRAJECTORY_DIR};
+use rl::ai::{Agent, InferenceMode};
+use rl::mdp::MarkovDecisionProcess;
 pub use splash::{IconPath, SplashPlugin};
+use std::error::Error;
 
+mod hud;
 mod menu;
+mod replay;
 mod splash;
 
 /// Enum class to determine who is playing the game: AI or human.
@@ -16,6 +23,8 @@ pub enum GameMode {
     AI,
     /// Human is playing.
     Human,
+    /// A recorded game is replayed.
+    Replay,
 }
 
 /// The state in which the game is.
@@ -36,7 +45,15 @@ pub enum GameState {
 pub fn default_plugin(app: &mut App) {
     app.init_state::<GameState>()
         .insert_state(GameMode::Human)
-        .add_systems(Startup, setup);
+        .init_resource::<AIMode>()
+        .init_resource::<RecordDemonstrations>()
+        .add_systems(Startup, setup)
+        .add_systems(Update, switch_ai_mode.run_if(in_state(GameMode::AI)))
+        .add_systems(
+            Update,
+            switch_demonstrations.run_if(in_state(GameMode::Human)),
+        )
+        .add_plugins(replay::replay_plugin);
 }
 
 /// Recursively despawn entities in the game.
@@ -56,6 +73,68 @@ fn setup(mut commands: Commands) {
 pub struct AIResource<T: MarkovDecisionProcess> {
     /// Smart pointer to the actual agent.
     pub nn: Box<dyn Agent<T> + Send + Sync>,
+
+    /// Random generator of the stochastic inference modes.
+    pub rng: StdRng,
+}
+
+impl<T: MarkovDecisionProcess> AIResource<T> {
+    /// Store the agent with a randomly seeded generator.
+    pub fn new(nn: Box<dyn Agent<T> + Send + Sync>) -> Self {
+        AIResource {
+            nn,
+            rng: StdRng::from_entropy(),
+        }
+    }
+
+    /// Action taken by the agent in the given inference mode.
+    pub fn act(&mut self, e: &T, mode: InferenceMode) -> Result<T::Action, Box<dyn Error>> {
+        self.nn.act(e, mode, &mut self.rng)
+    }
+}
+
+/// Probability of a random action when the AI plays in epsilon-greedy mode.
+pub const AI_EPSILON: f32 = 0.1;
+
+/// Way the AI picks its actions, kept from one game to the next.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct AIMode(pub InferenceMode);
+
+/// Switch the inference mode of the AI: G for greedy, S for sampled and E for epsilon-greedy.
+fn switch_ai_mode(keyboard_input: Res<ButtonInput<KeyCode>>, mut mode: ResMut<AIMode>) {
+    let new_mode = if keyboard_input.just_pressed(KeyCode::KeyG) {
+        InferenceMode::Greedy
+    } else if keyboard_input.just_pressed(KeyCode::KeyS) {
+        InferenceMode::Sampled
+    } else if keyboard_input.just_pressed(KeyCode::KeyE) {
+        InferenceMode::EpsilonGreedy(AI_EPSILON)
+    } else {
+        return;
+    };
+    if mode.0 != new_mode {
+        info!("AI plays in {new_mode:?} mode");
+        mode.0 = new_mode;
+    }
+}
+
+/// Save the human games in the demonstration directory as well, to train agents imitating
+/// the player. Kept from one game to the next.
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq)]
+pub struct RecordDemonstrations(pub bool);
+
+/// Switch the recording of demonstrations on and off with the D key.
+fn switch_demonstrations(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut record: ResMut<RecordDemonstrations>,
+) {
+    if keyboard_input.just_pressed(KeyCode::KeyD) {
+        record.0 = !record.0;
+        if record.0 {
+            info!("Human games are recorded as demonstrations in {DEMONSTRATION_DIR}");
+        } else {
+            info!("Human games are no longer recorded as demonstrations");
+        }
+    }
 }
 
 /// Remove the neural net fro resources and switch to Human Game mode.
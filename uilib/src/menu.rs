@@ -2,8 +2,12 @@ use crate::{despawn_screen, GameMode, GameState};
 use bevy::asset::embedded_asset;
 use bevy::{app::AppExit, prelude::*};
 
+/// Plugin displaying the main menu of the game.
 pub struct MenuPlugin {
+    /// Name of the game displayed on top of the menu.
     pub title: &'static str,
+
+    /// Colors of the menu.
     pub colors: Customization,
 }
 
@@ -25,7 +29,10 @@ impl Plugin for MenuPlugin {
                 Update,
                 (menu_action, button_system).run_if(in_state(GameState::Menu)),
             )
-            .add_systems(OnExit(GameState::Menu), despawn_screen::<MenuScreen>);
+            .add_systems(
+                OnExit(GameState::Menu),
+                (despawn_screen::<MenuScreen>, clear_message),
+            );
     }
 }
 #[derive(Component)]
@@ -35,27 +42,47 @@ struct MenuScreen;
 enum MenuButtonAction {
     Play,
     Aiplay,
+    Replay,
     Quit,
 }
 
+/// Colors of the menu buttons depending on the mouse interaction.
 #[derive(Clone, Copy)]
 pub struct ButtonColors {
+    /// Color of an idle button.
     pub normal: Color,
+
+    /// Color of a button under the mouse.
     pub howered: Color,
+
+    /// Color of a selected button under the mouse.
     pub howered_pressed: Color,
+
+    /// Color of a pressed button.
     pub pressed: Color,
 }
 
+/// Colors of the menu.
 #[derive(Resource, Clone, Copy)]
 pub struct Customization {
+    /// Color of the screen background.
     pub background: Color,
+
+    /// Colors of the buttons.
     pub buttons: ButtonColors,
+
+    /// Color of the square holding the title and the buttons.
     pub square: Color,
 }
 
 #[derive(Resource)]
 pub struct MenuTitle(&'static str);
 
+/// Message displayed under the title of the menu, such as the reason of a failure. It is
+/// removed when leaving the menu.
+#[derive(Resource)]
+pub struct MenuMessage(pub String);
+
 // Tag component used to mark which setting is currently selected
 #[derive(Component)]
 struct SelectedOption;
@@ -86,6 +113,7 @@ fn menu_setup(
     mut commands: Commands,
     colors: Res<Customization>,
     menu_title: Res<MenuTitle>,
+    message: Option<Res<MenuMessage>>,
     asset_server: Res<AssetServer>,
 ) {
     commands.insert_resource(ClearColor(colors.background));
@@ -154,8 +182,28 @@ fn menu_setup(
                         }),
                     );
 
-                    // Display three buttons for each action available from the main menu:
+                    // Display the message left for the player
+                    if let Some(message) = &message {
+                        parent.spawn(
+                            TextBundle::from_section(
+                                message.0.clone(),
+                                TextStyle {
+                                    font_size: 25.0,
+                                    color: Color::RED,
+                                    ..default()
+                                },
+                            )
+                            .with_style(Style {
+                                margin: UiRect::horizontal(Val::Px(50.0)),
+                                ..default()
+                            }),
+                        );
+                    }
+
+                    // Display a button for each action available from the main menu:
                     // - new game
+                    // - game played by the AI
+                    // - replay of a recorded game
                     // - quit
                     parent
                         .spawn((
@@ -199,6 +247,28 @@ fn menu_setup(
                             ));
                         });
 
+                    parent
+                        .spawn((
+                            ButtonBundle {
+                                style: button_style.clone(),
+                                background_color: colors.buttons.normal.into(),
+                                ..default()
+                            },
+                            MenuButtonAction::Replay,
+                        ))
+                        .with_children(|parent| {
+                            let icon = asset_server.load("embedded://uilib/buttons/play.png");
+                            parent.spawn(ImageBundle {
+                                style: button_icon_style.clone(),
+                                image: UiImage::new(icon),
+                                ..default()
+                            });
+                            parent.spawn(TextBundle::from_section(
+                                "Replay",
+                                button_text_style.clone(),
+                            ));
+                        });
+
                     parent
                         .spawn((
                             ButtonBundle {
@@ -221,6 +291,10 @@ fn menu_setup(
         });
 }
 
+fn clear_message(mut commands: Commands) {
+    commands.remove_resource::<MenuMessage>();
+}
+
 fn menu_action(
     interaction_query: Query<(&Interaction, &MenuButtonAction), Modified>,
     mut app_exit_events: EventWriter<AppExit>,
@@ -241,6 +315,10 @@ fn menu_action(
                     game_state.set(GameState::Playing);
                     game_mode.set(GameMode::AI);
                 }
+                MenuButtonAction::Replay => {
+                    game_state.set(GameState::Playing);
+                    game_mode.set(GameMode::Replay);
+                }
             }
         }
     }
@@ -0,0 +1,256 @@
+use crate::{despawn_screen, GameMode, GameState, MenuMessage};
+use bevy::prelude::*;
+use rfd::FileDialog;
+use rl::trajectory::{self, Trajectory};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Directory where the games played in the renderers are saved.
+pub const TRAJECTORY_DIR: &str = "trajectories";
+
+/// Directory where the human games are also saved when recording demonstrations.
+pub const DEMONSTRATION_DIR: &str = "demonstrations";
+
+// Bounds of the replay speed
+const MIN_SPEED: f32 = 0.125;
+const MAX_SPEED: f32 = 8.0;
+
+// Time skipped by the arrow keys in seconds
+const SEEK_STEP: f32 = 1.0;
+
+/// Recorded episodes being replayed, and the position of the replay in the current one.
+#[derive(Resource, Debug, Clone)]
+pub struct Replay {
+    trajectories: Vec<Trajectory>,
+    episode: usize,
+    times: Vec<f32>,
+
+    /// Time elapsed since the start of the episode, in seconds.
+    pub time: f32,
+
+    /// Speed of the replay relative to the recorded game.
+    pub speed: f32,
+
+    /// The replay is paused.
+    pub paused: bool,
+}
+
+impl Replay {
+    /// Replay the episodes from the start of the first one, or fail if there is none.
+    pub fn new(trajectories: Vec<Trajectory>) -> Result<Self, &'static str> {
+        let first = trajectories.first().ok_or("the file holds no episode")?;
+        let times = first.times();
+        Ok(Replay {
+            trajectories,
+            episode: 0,
+            times,
+            time: 0.0,
+            speed: 1.0,
+            paused: false,
+        })
+    }
+
+    /// Episode being replayed.
+    pub fn trajectory(&self) -> &Trajectory {
+        &self.trajectories[self.episode]
+    }
+
+    /// Index of the episode being replayed and number of episodes.
+    pub fn episode(&self) -> (usize, usize) {
+        (self.episode, self.trajectories.len())
+    }
+
+    /// Replay another episode from its start. The index is clamped to the recorded episodes.
+    pub fn select_episode(&mut self, i: usize) {
+        self.episode = i.min(self.trajectories.len() - 1);
+        self.times = self.trajectory().times();
+        self.time = 0.0;
+    }
+
+    /// Number of steps played at the current time.
+    pub fn step(&self) -> usize {
+        self.times.partition_point(|t| *t <= self.time).max(1) - 1
+    }
+
+    /// Features of the state displayed at the current time.
+    pub fn feature(&self) -> &[f32] {
+        self.trajectory().feature(self.step())
+    }
+
+    /// Duration of the episode being replayed.
+    pub fn duration(&self) -> f32 {
+        self.times.last().copied().unwrap_or_default()
+    }
+
+    /// Move the replay by the given time, clamped to the episode.
+    pub fn seek(&mut self, dt: f32) {
+        self.time = (self.time + dt).clamp(0.0, self.duration());
+    }
+
+    /// Move the replay forward by the given real time, unless it is paused. The replay pauses
+    /// at the end of the episode.
+    pub fn advance(&mut self, dt: f32) {
+        if self.paused {
+            return;
+        }
+        self.seek(self.speed * dt);
+        if self.time >= self.duration() {
+            self.paused = true;
+        }
+    }
+}
+
+#[derive(Component)]
+struct ReplayText;
+
+/// Add the replay of the recorded games, played in `GameMode::Replay`. The renderers display
+/// the state of the `Replay` resource.
+pub(crate) fn replay_plugin(app: &mut App) {
+    app.add_systems(OnEnter(GameMode::Replay), load_replay)
+        .add_systems(
+            Update,
+            (replay_controls, replay_text_update_system)
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<Replay>),
+        )
+        .add_systems(
+            FixedPreUpdate,
+            advance_replay
+                .run_if(in_state(GameState::Playing))
+                .run_if(resource_exists::<Replay>),
+        )
+        .add_systems(
+            OnExit(GameState::Playing),
+            (despawn_screen::<ReplayText>, stop_replay).run_if(in_state(GameMode::Replay)),
+        );
+}
+
+fn load_replay(
+    mut commands: Commands,
+    mut game_state: ResMut<NextState<GameState>>,
+    mut game_mode: ResMut<NextState<GameMode>>,
+) {
+    // Picking the file storing the games
+    let Some(file) = FileDialog::new()
+        .add_filter("Recorded games", &["jsonl"])
+        .set_directory(TRAJECTORY_DIR)
+        .pick_file()
+    else {
+        info!("No file picked. Return to main menu.");
+        game_state.set(GameState::Menu);
+        game_mode.set(GameMode::Human);
+        return;
+    };
+
+    match trajectory::load(file)
+        .map_err(|e| e.to_string())
+        .and_then(|t| Replay::new(t).map_err(str::to_owned))
+    {
+        Ok(replay) => {
+            commands.insert_resource(replay);
+            commands.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font_size: 20.0,
+                        color: Color::BLACK,
+                        ..default()
+                    },
+                )
+                .with_style(Style {
+                    position_type: PositionType::Absolute,
+                    right: Val::Px(10.0),
+                    top: Val::Px(10.0),
+                    ..default()
+                }),
+                ReplayText,
+            ));
+        }
+        Err(e) => {
+            error!("The games could not be loaded: {e}");
+            commands.insert_resource(MenuMessage(format!("The games could not be loaded: {e}")));
+            game_state.set(GameState::Menu);
+            game_mode.set(GameMode::Human);
+        }
+    }
+}
+
+/// Control the replay: space to pause, left and right arrows to seek, up and down arrows to
+/// change the speed, page up and page down to change the episode, and escape to leave.
+fn replay_controls(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut replay: ResMut<Replay>,
+    mut game_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        if replay.time >= replay.duration() {
+            replay.time = 0.0;
+        }
+        replay.paused = !replay.paused;
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowLeft) {
+        replay.seek(-SEEK_STEP);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowRight) {
+        replay.seek(SEEK_STEP);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowUp) {
+        replay.speed = (2.0 * replay.speed).min(MAX_SPEED);
+    }
+    if keyboard_input.just_pressed(KeyCode::ArrowDown) {
+        replay.speed = (replay.speed / 2.0).max(MIN_SPEED);
+    }
+    let (episode, _) = replay.episode();
+    if keyboard_input.just_pressed(KeyCode::PageDown) {
+        replay.select_episode(episode + 1);
+    }
+    if keyboard_input.just_pressed(KeyCode::PageUp) {
+        replay.select_episode(episode.saturating_sub(1));
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        game_state.set(GameState::Menu);
+    }
+}
+
+fn advance_replay(mut replay: ResMut<Replay>, time: Res<Time<Fixed>>) {
+    replay.advance(time.delta_seconds());
+}
+
+fn replay_text_update_system(mut query: Query<&mut Text, With<ReplayText>>, replay: Res<Replay>) {
+    let (episode, nb_episodes) = replay.episode();
+    for mut text in &mut query {
+        text.sections[0].value = format!(
+            "Episode {}/{nb_episodes}  {:.1}/{:.1} s  x{}{}",
+            episode + 1,
+            replay.time,
+            replay.duration(),
+            replay.speed,
+            if replay.paused { "  (paused)" } else { "" },
+        );
+    }
+}
+
+fn stop_replay(mut commands: Commands, mut game_mode: ResMut<NextState<GameMode>>) {
+    commands.remove_resource::<Replay>();
+    game_mode.set(GameMode::Human);
+}
+
+/// Save the recorded episodes in a new file of the directory, named after the game and the
+/// current time. Nothing is saved if no step was played.
+pub fn save_trajectories(dir: &str, game: &str, trajectories: &[Trajectory]) {
+    if trajectories.is_empty() {
+        return;
+    }
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = PathBuf::from(dir).join(format!("{game}-{secs}.jsonl"));
+    match std::fs::create_dir_all(dir)
+        .map_err(Into::into)
+        .and_then(|_| trajectory::save(trajectories, &path))
+    {
+        Ok(()) => info!("Game saved in {}", path.display()),
+        Err(e) => error!("The game could not be saved: {e}"),
+    }
+}